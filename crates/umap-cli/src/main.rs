@@ -1,14 +1,19 @@
-use std::{fs, net::SocketAddr, path::PathBuf};
+use std::{
+    fs,
+    net::SocketAddr,
+    path::{Path, PathBuf},
+    sync::Arc,
+};
 
-use anyhow::{Context, Result, anyhow};
+use anyhow::{Context, Result};
 use axum::{
     Json, Router,
     extract::{Query, State},
     http::StatusCode,
-    response::IntoResponse,
+    response::{IntoResponse, Response},
     routing::get,
 };
-use clap::{Parser, Subcommand};
+use clap::{Args, Parser, Subcommand};
 use ndarray::Array2;
 use serde::Deserialize;
 use tower_http::{
@@ -19,13 +24,67 @@ use tower_http::{
 use tracing::{Level, info};
 
 use umap_core::{
-    Db, HasherEmbedder, Point2D, Point3D, UmapParams, chunk_by_token_overlap, reduce_default,
-    top_k_by_cosine, umap_reduce_cosine,
+    CachedEmbedder, ChunkerConfig, Db, Embedder, EmbedderConfig, EmbeddingCache, Point2D, Point3D,
+    PreprocessChain, TokenizerConfig, UmapError, UmapParams, VectorStorage, build_chunker,
+    build_embedder, procrustes_align, reducer_by_name, top_k_rescored,
 };
 
 #[derive(Clone)]
 struct AppState {
     db_path: String,
+    // Default preprocessing chain, overridable per request
+    preprocess: String,
+    cache: Arc<EmbeddingCache>,
+}
+
+/// Handler error carrying the HTTP status that matches the underlying failure.
+struct ApiError {
+    status: StatusCode,
+    kind: &'static str,
+    message: String,
+}
+
+impl From<UmapError> for ApiError {
+    fn from(e: UmapError) -> Self {
+        let status = match &e {
+            UmapError::InvalidParameter(_) | UmapError::DimensionMismatch { .. } => {
+                StatusCode::BAD_REQUEST
+            }
+            UmapError::EmbedderMismatch { .. } => StatusCode::CONFLICT,
+            UmapError::Numerical(_) => StatusCode::UNPROCESSABLE_ENTITY,
+            UmapError::Remote(_) => StatusCode::BAD_GATEWAY,
+            UmapError::CorruptVector { .. }
+            | UmapError::CorruptCollection(_)
+            | UmapError::Storage(_)
+            | UmapError::Io(_) => StatusCode::INTERNAL_SERVER_ERROR,
+        };
+        Self {
+            status,
+            kind: e.kind(),
+            message: e.to_string(),
+        }
+    }
+}
+
+/// Run a handler's blocking work (SQLite, embedding, layout) on the blocking
+/// thread pool so slow embedders and retry backoff do not stall the runtime.
+async fn blocking<T: Send + 'static>(
+    work: impl FnOnce() -> Result<T, ApiError> + Send + 'static,
+) -> Result<T, ApiError> {
+    tokio::task::spawn_blocking(work).await.unwrap_or_else(|e| {
+        Err(ApiError {
+            status: StatusCode::INTERNAL_SERVER_ERROR,
+            kind: "internal",
+            message: format!("handler task failed: {e}"),
+        })
+    })
+}
+
+impl IntoResponse for ApiError {
+    fn into_response(self) -> Response {
+        let body = serde_json::json!({ "error": self.message, "kind": self.kind });
+        (self.status, Json(body)).into_response()
+    }
 }
 
 #[derive(Parser, Debug)]
@@ -43,12 +102,14 @@ enum Commands {
         db: PathBuf,
         #[arg(long)]
         file: PathBuf,
-        #[arg(long, default_value_t = 512)]
-        dim: usize,
-        #[arg(long, default_value_t = 1000)]
-        tokens_per_chunk: usize,
-        #[arg(long, default_value_t = 300)]
-        overlap: usize,
+        #[command(flatten)]
+        embedder: EmbedderArgs,
+        #[command(flatten)]
+        cache: CacheArgs,
+        #[command(flatten)]
+        storage: StorageArgs,
+        #[command(flatten)]
+        chunker: ChunkerArgs,
     },
 
     /// Start the HTTP server and optionally serve a static UI directory
@@ -59,6 +120,11 @@ enum Commands {
         addr: SocketAddr,
         #[arg(long)]
         static_dir: Option<PathBuf>,
+        /// Default vector preprocessing chain, e.g. "center,normalize,pca:50"
+        #[arg(long, default_value = "")]
+        preprocess: String,
+        #[command(flatten)]
+        cache: CacheArgs,
     },
 
     /// Quick CLI nearest-neighbor search
@@ -69,11 +135,363 @@ enum Commands {
         query: String,
         #[arg(long, default_value_t = 20)]
         k: usize,
-        #[arg(long, default_value_t = 512)]
-        dim: usize,
+        /// Re-rank this many quantized candidates at full precision (0 = off)
+        #[arg(long, default_value_t = 0)]
+        rescore: usize,
+        #[command(flatten)]
+        embedder: EmbedderArgs,
+        #[command(flatten)]
+        cache: CacheArgs,
     },
 }
 
+/// Embedding cache settings shared by every command that embeds text.
+#[derive(Args, Debug)]
+struct CacheArgs {
+    /// Vectors kept in the in-memory embedding cache; 0 disables it
+    #[arg(long, default_value_t = 10_000)]
+    cache_size: usize,
+    /// Also persist cached vectors in the database's embedding_cache table
+    #[arg(long)]
+    persist_cache: bool,
+}
+
+impl CacheArgs {
+    fn build(&self, db_path: &str) -> Result<Arc<EmbeddingCache>, UmapError> {
+        let mut cache = EmbeddingCache::new(self.cache_size);
+        if self.persist_cache {
+            cache = cache.with_store(db_path)?;
+        }
+        Ok(Arc::new(cache))
+    }
+}
+
+/// Chunking strategy and its parameters.
+#[derive(Args, Debug)]
+struct ChunkerArgs {
+    /// Chunking strategy: token, paragraph, sentence, recursive, markdown, semantic or code
+    #[arg(long, default_value = "token")]
+    chunker: String,
+    /// Token / recursive / markdown / semantic: words per chunk; code: words per item before it is split
+    #[arg(long, default_value_t = 1000)]
+    tokens_per_chunk: usize,
+    /// Token / recursive / markdown: words shared by consecutive chunks
+    #[arg(long, default_value_t = 300)]
+    overlap: usize,
+    /// Sentence: sentences per chunk; semantic: sentences compared on each side of a boundary
+    #[arg(long, default_value_t = 5)]
+    window: usize,
+    /// Semantic: split at boundaries in this lowest percentile of similarity (0 = size only)
+    #[arg(long, default_value_t = 10)]
+    percentile: usize,
+    /// Semantic: words a chunk needs before it may end at a topic shift
+    #[arg(long, default_value_t = 100)]
+    min_tokens: usize,
+    /// Code: rust, python, typescript or tsx [default: from the file extension]
+    #[arg(long)]
+    language: Option<String>,
+    /// Strip Project Gutenberg license header/footer, unwrap lines and record chapters
+    #[arg(long)]
+    gutenberg: bool,
+}
+
+impl ChunkerArgs {
+    fn config(&self, file: &Path) -> ChunkerConfig {
+        ChunkerConfig {
+            name: self.chunker.clone(),
+            tokens_per_chunk: self.tokens_per_chunk,
+            overlap: self.overlap,
+            window: self.window,
+            percentile: self.percentile,
+            min_tokens: self.min_tokens,
+            language: self.language.clone().or_else(|| extension(file)),
+            gutenberg: self.gutenberg,
+        }
+    }
+}
+
+/// Lowercased file extension, the code chunker's default language.
+fn extension(file: &Path) -> Option<String> {
+    file.extension().map(|e| e.to_string_lossy().to_lowercase())
+}
+
+/// How a new collection stores its vectors; fixed once chunks exist.
+#[derive(Args, Debug)]
+struct StorageArgs {
+    /// Vector encoding: "none" (f32), "int8" or "binary" [default: collection's, else none]
+    #[arg(long)]
+    quantize: Option<String>,
+    /// Also keep f32 vectors so searches can rescore quantized candidates
+    #[arg(long)]
+    keep_full_precision: Option<bool>,
+}
+
+impl StorageArgs {
+    /// Overlay the given flags on the collection's recorded storage.
+    fn resolve(&self, db: &Db) -> Result<VectorStorage, UmapError> {
+        let mut storage = db.vector_storage()?;
+        if let Some(q) = &self.quantize {
+            storage.quantization = q.parse()?;
+        }
+        if let Some(keep) = self.keep_full_precision {
+            storage.keep_full_precision = keep;
+        }
+        Ok(storage)
+    }
+}
+
+fn log_cache_stats(cache: &EmbeddingCache) {
+    let stats = cache.stats();
+    info!(
+        "embedding cache: {} hits ({} memory, {} stored), {} misses",
+        stats.hits(),
+        stats.memory_hits,
+        stats.store_hits,
+        stats.misses
+    );
+}
+
+/// Embedder selection shared by `ingest` and `search`; every field falls back
+/// to the collection's recorded embedder, then to the defaults.
+#[derive(Args, Debug, Default)]
+struct EmbedderArgs {
+    /// Embedder implementation by name: hasher, charngram, tfidf, lsa, wordvec,
+    /// transformer, remote
+    #[arg(long)]
+    embedder: Option<String>,
+    /// Vector dimension [default: 512]; for remote, the model's output size
+    /// (asked from the server when not given)
+    #[arg(long)]
+    dim: Option<usize>,
+    /// TF-IDF: dampen repeated terms with 1 + ln(tf)
+    #[arg(long)]
+    sublinear_tf: Option<bool>,
+    /// TF-IDF: "hashing" or "learned"
+    #[arg(long)]
+    vocabulary: Option<String>,
+    /// Char n-grams: n-gram lengths, e.g. 3-5 [default: 3-5]
+    #[arg(long)]
+    ngrams: Option<String>,
+    /// Char n-grams: weight of whole-word hashing in 0..=1 [default: 0]
+    #[arg(long)]
+    word_weight: Option<f32>,
+    /// LSA: TF-IDF space size the SVD is fitted in [default: 4096]
+    #[arg(long)]
+    vocab_size: Option<usize>,
+    /// Word vectors: GloVe/word2vec/fastText file (.txt, .vec, .bin)
+    #[arg(long)]
+    vectors: Option<String>,
+    /// Word vectors: "mean" or "sif" token weighting
+    #[arg(long)]
+    pooling: Option<String>,
+    /// Transformer: model directory with config.json, tokenizer.json and weights
+    #[arg(long)]
+    model_dir: Option<String>,
+    /// Transformer: L2-normalize pooled embeddings [default: true]
+    #[arg(long)]
+    normalize: Option<bool>,
+    /// Transformer / remote: texts per forward pass or request [default: 32]
+    #[arg(long)]
+    batch_size: Option<usize>,
+    /// Remote: "openai" (/v1/embeddings) or "ollama" (/api/embeddings)
+    #[arg(long)]
+    api: Option<String>,
+    /// Remote: server base URL, e.g. http://localhost:11434
+    #[arg(long)]
+    endpoint: Option<String>,
+    /// Remote: model name sent to the server
+    #[arg(long)]
+    model: Option<String>,
+    /// Remote: per-request timeout in seconds [default: 30]
+    #[arg(long)]
+    timeout_secs: Option<u64>,
+    /// Remote: retries on connection errors, 429 and 5xx [default: 3]
+    #[arg(long)]
+    max_retries: Option<u32>,
+    /// Tokenizer: "simple" or "unicode" (UAX #29 words, CJK character bigrams)
+    #[arg(long)]
+    segmentation: Option<String>,
+    /// Tokenizer: apply Unicode NFKC normalization before lowercasing
+    #[arg(long)]
+    nfkc: Option<bool>,
+    /// Tokenizer: drop stopwords of this language (e.g. english, de), or "none"
+    #[arg(long)]
+    stopwords: Option<String>,
+    /// Tokenizer: Snowball-stem words in this language (e.g. english, fr), or "none"
+    #[arg(long)]
+    stem: Option<String>,
+    /// Tokenizer: also emit character n-grams, e.g. 3-5, or "none"
+    #[arg(long)]
+    char_ngrams: Option<String>,
+}
+
+impl EmbedderArgs {
+    /// Start from the embedder recorded for the collection (or the default for
+    /// a new one) and apply explicit overrides; `Db::check_embedder` then
+    /// rejects overrides that would mix incompatible vectors.
+    fn resolve(&self, db: &Db) -> Result<EmbedderConfig, UmapError> {
+        let stored = db.embedder_info()?.map(|info| info.config);
+        let mut config = stored.clone().unwrap_or_default();
+        if let Some(name) = &self.embedder {
+            config.name = name.clone();
+        }
+        if let Some(dim) = self.dim {
+            config.dim = dim;
+        }
+        if let Some(sublinear_tf) = self.sublinear_tf {
+            config.sublinear_tf = sublinear_tf;
+        }
+        if let Some(vocabulary) = &self.vocabulary {
+            config.vocabulary = vocabulary.parse()?;
+        }
+        if let Some(ngrams) = &self.ngrams {
+            config.ngrams = TokenizerConfig::parse_ngram_range(ngrams)?;
+        }
+        if let Some(word_weight) = self.word_weight {
+            config.word_weight = word_weight;
+        }
+        if let Some(vocab_size) = self.vocab_size {
+            config.vocab_size = vocab_size;
+        }
+        if let Some(path) = &self.vectors {
+            config.path = Some(path.clone());
+        }
+        if let Some(pooling) = &self.pooling {
+            config.pooling = pooling.parse()?;
+        }
+        if let Some(dir) = &self.model_dir {
+            config.path = Some(dir.clone());
+        }
+        if let Some(normalize) = self.normalize {
+            config.normalize = normalize;
+        }
+        if let Some(batch_size) = self.batch_size {
+            config.batch_size = batch_size;
+        }
+        if let Some(api) = &self.api {
+            config.api = api.parse()?;
+        }
+        if let Some(endpoint) = &self.endpoint {
+            config.endpoint = Some(endpoint.clone());
+        }
+        if let Some(model) = &self.model {
+            config.model = Some(model.clone());
+        }
+        if let Some(timeout_secs) = self.timeout_secs {
+            config.timeout_secs = timeout_secs;
+        }
+        if let Some(max_retries) = self.max_retries {
+            config.max_retries = max_retries;
+        }
+        if let Some(segmentation) = &self.segmentation {
+            config.tokenizer.segmentation = segmentation.parse()?;
+        }
+        if let Some(nfkc) = self.nfkc {
+            config.tokenizer.nfkc = nfkc;
+        }
+        if let Some(lang) = &self.stopwords {
+            config.tokenizer.stopwords = parse_optional(lang, |s| s.parse())?;
+        }
+        if let Some(lang) = &self.stem {
+            config.tokenizer.stem = parse_optional(lang, |s| s.parse())?;
+        }
+        if let Some(range) = &self.char_ngrams {
+            config.tokenizer.char_ngrams =
+                parse_optional(range, TokenizerConfig::parse_ngram_range)?;
+        }
+        // A remote model's size is only known from a collection built with it;
+        // otherwise the server is asked
+        let same_remote = stored.is_some_and(|s| {
+            s.name == config.name
+                && s.api == config.api
+                && s.endpoint == config.endpoint
+                && s.model == config.model
+        });
+        if config.name.eq_ignore_ascii_case("remote") && self.dim.is_none() && !same_remote {
+            config.dim = 0;
+        }
+        Ok(config)
+    }
+}
+
+/// `"none"` clears an optional setting; anything else is parsed.
+fn parse_optional<T>(
+    value: &str,
+    parse: impl FnOnce(&str) -> Result<T, UmapError>,
+) -> Result<Option<T>, UmapError> {
+    if value.eq_ignore_ascii_case("none") {
+        Ok(None)
+    } else {
+        parse(value).map(Some)
+    }
+}
+
+/// Build the embedder the collection calls for, restore its fitted state and
+/// put it behind the shared embedding cache. An embedder the collection was
+/// not built with is rejected before its state is read.
+fn open_embedder(
+    db: &Db,
+    args: &EmbedderArgs,
+    cache: &Arc<EmbeddingCache>,
+) -> Result<Box<dyn Embedder>, UmapError> {
+    let mut embedder = build_embedder(&args.resolve(db)?)?;
+    db.check_embedder(&embedder.info())?;
+    embedder.load_state(db)?;
+    Ok(Box::new(CachedEmbedder::new(embedder, cache.clone())))
+}
+
+/// Chunk `text` as `chunking` says, embed the chunks and store them under
+/// `source`. Returns the number of chunks.
+///
+/// Fitting and storing happen in one write transaction, with the embedder's
+/// corpus state reloaded inside it, so concurrent ingests into a collection
+/// never lose each other's statistics and a failed ingest leaves none behind.
+fn ingest_text(
+    db: &Db,
+    embedder: &mut dyn Embedder,
+    chunking: &ChunkerConfig,
+    source: &str,
+    text: &str,
+) -> Result<usize, UmapError> {
+    db.check_embedder(&embedder.info())?;
+    // The chunker may embed with the embedder, so it is done before fitting
+    let (chunks, name, params) = {
+        let chunker = build_chunker(chunking, &*embedder)?;
+        let chunks = chunker.chunk(text)?;
+        (chunks, chunker.name().to_string(), chunker.params())
+    };
+    info!(
+        "ingesting {} {} chunks from {} with {}",
+        chunks.len(),
+        name,
+        source,
+        embedder.name()
+    );
+    let texts: Vec<&str> = chunks.iter().map(|c| c.text.as_str()).collect();
+    // Stateless embedders may write to an embedding cache kept in the same
+    // database file, so they embed before this connection takes the lock
+    let mut vectors = if embedder.cacheable() {
+        Some(embedder.embed_batch(&texts)?)
+    } else {
+        None
+    };
+    db.write_transaction(|| {
+        db.ensure_embedder(&embedder.info())?;
+        embedder.load_state(db)?;
+        embedder.fit(&texts)?;
+        embedder.save_state(db)?;
+        let vectors = match vectors.take() {
+            Some(v) => v,
+            None => embedder.embed_batch(&texts)?,
+        };
+        for (i, (ch, v)) in chunks.iter().zip(&vectors).enumerate() {
+            db.insert_chunk(source, i as i64, ch, v)?;
+        }
+        db.record_source(source, &name, &params, chunks.len() as i64)
+    })?;
+    Ok(chunks.len())
+}
+
 #[tokio::main]
 async fn main() -> Result<()> {
     tracing_subscriber::fmt()
@@ -87,63 +505,104 @@ async fn main() -> Result<()> {
         Commands::Ingest {
             db,
             file,
-            dim,
-            tokens_per_chunk,
-            overlap,
-        } => cmd_ingest(db, file, dim, tokens_per_chunk, overlap).await,
+            embedder,
+            cache,
+            storage,
+            chunker,
+        } => cmd_ingest(db, file, embedder, cache, storage, chunker).await,
         Commands::Serve {
             db,
             addr,
             static_dir,
-        } => cmd_serve(db, addr, static_dir).await,
-        Commands::Search { db, query, k, dim } => cmd_search(db, query, k, dim).await,
+            preprocess,
+            cache,
+        } => {
+            PreprocessChain::parse(&preprocess)?;
+            cmd_serve(db, addr, static_dir, preprocess, cache).await
+        }
+        Commands::Search {
+            db,
+            query,
+            k,
+            rescore,
+            embedder,
+            cache,
+        } => cmd_search(db, query, k, rescore, embedder, cache).await,
     }
 }
 
 async fn cmd_ingest(
     db_path: PathBuf,
     file: PathBuf,
-    dim: usize,
-    tokens_per_chunk: usize,
-    overlap: usize,
+    embedder_args: EmbedderArgs,
+    cache_args: CacheArgs,
+    storage_args: StorageArgs,
+    chunker_args: ChunkerArgs,
 ) -> Result<()> {
     let db = Db::open(db_path.to_str().unwrap())?;
+    db.ensure_vector_storage(&storage_args.resolve(&db)?)?;
+    let cache = cache_args.build(db_path.to_str().unwrap())?;
     let text = fs::read_to_string(&file).with_context(|| format!("read file {:?}", file))?;
-    let chunks = chunk_by_token_overlap(&text, tokens_per_chunk, overlap);
-    let embedder = HasherEmbedder::new(dim);
-    info!("ingesting {} chunks from {:?}", chunks.len(), file);
-    for (i, ch) in chunks.iter().enumerate() {
-        let v = embedder.embed(ch);
-        db.insert_chunk(file.to_string_lossy().as_ref(), i as i64, ch, &v)?;
-    }
+    let mut embedder = open_embedder(&db, &embedder_args, &cache)?;
+    ingest_text(
+        &db,
+        embedder.as_mut(),
+        &chunker_args.config(&file),
+        file.to_string_lossy().as_ref(),
+        &text,
+    )?;
     info!("ingest complete; total rows = {}", db.count_chunks()?);
+    log_cache_stats(&cache);
     Ok(())
 }
 
-async fn cmd_search(db_path: PathBuf, query: String, k: usize, dim: usize) -> Result<()> {
+async fn cmd_search(
+    db_path: PathBuf,
+    query: String,
+    k: usize,
+    rescore: usize,
+    embedder_args: EmbedderArgs,
+    cache_args: CacheArgs,
+) -> Result<()> {
     let db = Db::open(db_path.to_str().unwrap())?;
-    let embedder = HasherEmbedder::new(dim);
-    let qvec = embedder.embed(&query);
-    let top = top_k_by_cosine(&db, &qvec, k)?;
+    let cache = cache_args.build(db_path.to_str().unwrap())?;
+    let embedder = open_embedder(&db, &embedder_args, &cache)?;
+    db.check_embedder(&embedder.info())?;
+    let qvec = embedder.embed(&query)?;
+    log_cache_stats(&cache);
+    let top = top_k_rescored(&db, &qvec, k, rescore)?;
     println!("Top {} results:", top.len());
     for (rank, sc) in top.iter().enumerate() {
+        let lines = match sc.record.span {
+            Some(span) => format!(" L{}-{}", span.start_line, span.end_line),
+            None => String::new(),
+        };
+        let symbol = sc
+            .record
+            .symbol
+            .as_deref()
+            .map(|s| format!(" {s}"))
+            .unwrap_or_default();
         println!(
-            "#{} score={:.3} [{}:{}] {}",
+            "#{} score={:.3} [{}:{}{}{}] {}",
             rank + 1,
             sc.score,
             sc.record.source,
             sc.record.chunk_index,
+            lines,
+            symbol,
             preview(&sc.record.text, 120)
         );
     }
     Ok(())
 }
 
+/// First `n` characters of `s` on one line, whitespace runs collapsed.
 fn preview(s: &str, n: usize) -> String {
-    if s.len() <= n {
-        s.to_string()
-    } else {
-        format!("{}…", &s[..n])
+    let flat = s.split_whitespace().collect::<Vec<_>>().join(" ");
+    match flat.char_indices().nth(n) {
+        Some((cut, _)) => format!("{}…", &flat[..cut]),
+        None => flat,
     }
 }
 
@@ -152,12 +611,33 @@ struct SearchParams {
     query: String,
     #[serde(default = "default_k")]
     k: usize,
+    // Quantized candidates to re-rank at full precision; 0 disables
+    #[serde(default)]
+    rescore: usize,
     #[serde(default = "default_dims")]
     dims: usize,
-    #[serde(default = "default_dim")]
-    dim: usize,
+    // Embedder overrides; the collection's recorded embedder is used when absent
+    #[serde(default)]
+    dim: Option<usize>,
+    #[serde(default)]
+    embedder: Option<String>,
+    #[serde(default)]
+    sublinear_tf: Option<bool>,
+    #[serde(default)]
+    vocabulary: Option<String>,
+    #[serde(default)]
+    ngrams: Option<String>,
+    #[serde(default)]
+    word_weight: Option<f32>,
+    #[serde(default)]
+    vocab_size: Option<usize>,
+    #[serde(default)]
+    pooling: Option<String>,
     #[serde(default = "default_method")]
     method: String,
+    // Preprocessing chain; falls back to the server default when absent
+    #[serde(default)]
+    preprocess: Option<String>,
     // UMAP params
     #[serde(default = "default_n_neighbors")]
     n_neighbors: usize,
@@ -177,6 +657,18 @@ struct SearchParams {
     repulsion_strength: f32,
     #[serde(default = "default_seed")]
     random_state: u64,
+    // JSON list of {id, x, y[, z]} from a previous layout to align the result to
+    #[serde(default)]
+    align_to: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+struct AnchorPoint {
+    id: i64,
+    x: f32,
+    y: f32,
+    #[serde(default)]
+    z: Option<f32>,
 }
 
 fn default_k() -> usize {
@@ -185,9 +677,6 @@ fn default_k() -> usize {
 fn default_dims() -> usize {
     2
 }
-fn default_dim() -> usize {
-    512
-}
 fn default_method() -> String {
     "umap".to_string()
 }
@@ -219,14 +708,25 @@ fn default_seed() -> u64 {
     42
 }
 
-async fn cmd_serve(db_path: PathBuf, addr: SocketAddr, static_dir: Option<PathBuf>) -> Result<()> {
+async fn cmd_serve(
+    db_path: PathBuf,
+    addr: SocketAddr,
+    static_dir: Option<PathBuf>,
+    preprocess: String,
+    cache_args: CacheArgs,
+) -> Result<()> {
+    let db_path = db_path.to_string_lossy().to_string();
     let state = AppState {
-        db_path: db_path.to_string_lossy().to_string(),
+        cache: cache_args.build(&db_path)?,
+        db_path,
+        preprocess,
     };
 
     let mut app = Router::new()
         .route("/api/search", get(api_search))
         .route("/api/ingest_text", axum::routing::post(api_ingest_text))
+        .route("/api/cache_stats", get(api_cache_stats))
+        .route("/api/sources", get(api_sources))
         .with_state(state.clone())
         .layer(CorsLayer::permissive())
         .layer(TraceLayer::new_for_http());
@@ -250,39 +750,97 @@ async fn api_search(
     Query(params): Query<SearchParams>,
 ) -> impl IntoResponse {
     let start = std::time::Instant::now();
-    let res = (|| -> Result<axum::response::Response> {
+    let res = blocking(move || -> Result<Response, ApiError> {
         if params.dims != 2 && params.dims != 3 {
-            return Err(anyhow!("dims must be 2 or 3"));
+            return Err(UmapError::InvalidParameter("dims must be 2 or 3".into()).into());
         }
-        let embedder = HasherEmbedder::new(params.dim);
-        let qvec = embedder.embed(&params.query);
         let db = Db::open(&state.db_path)?;
-        let top = top_k_by_cosine(&db, &qvec, params.k)?;
+        let embedder = open_embedder(
+            &db,
+            &EmbedderArgs {
+                embedder: params.embedder.clone(),
+                dim: params.dim,
+                sublinear_tf: params.sublinear_tf,
+                vocabulary: params.vocabulary.clone(),
+                ngrams: params.ngrams.clone(),
+                word_weight: params.word_weight,
+                vocab_size: params.vocab_size,
+                pooling: params.pooling.clone(),
+                // File paths and endpoints are only taken from the CLI, never from clients
+                ..EmbedderArgs::default()
+            },
+            &state.cache,
+        )?;
+        db.check_embedder(&embedder.info())?;
+        let qvec = embedder.embed(&params.query)?;
+        let top = top_k_rescored(&db, &qvec, params.k, params.rescore)?;
+        let dim = embedder.dim();
         let mat = Array2::from_shape_vec(
-            (top.len(), params.dim),
+            (top.len(), dim),
             top.iter().flat_map(|s| s.record.vector.clone()).collect(),
         )
-        .map_err(|e| anyhow!("array shape error: {e}"))?;
-        let reduced = if params.method.to_lowercase() == "pca" {
-            reduce_default(&mat, params.dims)?
-        } else {
-            let uparams = UmapParams {
-                n_neighbors: params.n_neighbors,
-                n_epochs: params.n_epochs,
-                min_dist: params.min_dist,
-                spread: params.spread,
-                learning_rate: params.learning_rate,
-                negative_sample_rate: params.negative_sample_rate,
-                set_op_mix_ratio: params.set_op_mix_ratio,
-                repulsion_strength: params.repulsion_strength,
-                random_state: params.random_state,
-            };
-            umap_reduce_cosine(&mat, params.dims, &uparams)?
+        .map_err(|_| {
+            let actual = top
+                .iter()
+                .map(|s| s.record.dim)
+                .find(|d| *d != dim)
+                .unwrap_or(dim);
+            UmapError::DimensionMismatch {
+                expected: dim,
+                actual,
+            }
+        })?;
+        let chain =
+            PreprocessChain::parse(params.preprocess.as_deref().unwrap_or(&state.preprocess))?;
+        let mat = chain.apply(&mat)?;
+        let uparams = UmapParams {
+            n_neighbors: params.n_neighbors,
+            n_epochs: params.n_epochs,
+            min_dist: params.min_dist,
+            spread: params.spread,
+            learning_rate: params.learning_rate,
+            negative_sample_rate: params.negative_sample_rate,
+            set_op_mix_ratio: params.set_op_mix_ratio,
+            repulsion_strength: params.repulsion_strength,
+            random_state: params.random_state,
+        };
+        let reducer = reducer_by_name(&params.method, &uparams)?;
+        let reduced = reducer.reduce(&mat, params.dims)?;
+
+        let mut alignment = serde_json::Value::Null;
+        let reduced = match params.align_to.as_deref().map(str::trim) {
+            Some(spec) if !spec.is_empty() => {
+                let anchors: Vec<AnchorPoint> = serde_json::from_str(spec)
+                    .map_err(|e| UmapError::InvalidParameter(format!("invalid align_to: {e}")))?;
+                let mut prev = Array2::<f32>::zeros((anchors.len(), params.dims));
+                for (i, a) in anchors.iter().enumerate() {
+                    prev[(i, 0)] = a.x;
+                    prev[(i, 1)] = a.y;
+                    if params.dims == 3 {
+                        prev[(i, 2)] = a.z.ok_or_else(|| {
+                            UmapError::InvalidParameter(format!(
+                                "align_to point {} is missing z",
+                                a.id
+                            ))
+                        })?;
+                    }
+                }
+                let prev_ids: Vec<i64> = anchors.iter().map(|a| a.id).collect();
+                let ids: Vec<i64> = top.iter().map(|s| s.record.id).collect();
+                let res = procrustes_align(&reduced, &ids, &prev, &prev_ids)?;
+                alignment = serde_json::json!({
+                    "matched": res.matched,
+                    "disparity": res.disparity,
+                });
+                res.layout
+            }
+            _ => reduced,
         };
 
         let took = start.elapsed();
         info!(
             method = %params.method,
+            preprocess = ?chain.names(),
             dims = params.dims,
             k = params.k,
             n_neighbors = params.n_neighbors,
@@ -302,13 +860,20 @@ async fn api_search(
                     chunk_index: sc.record.chunk_index,
                     score: sc.score,
                     text_preview: preview(&sc.record.text, 160),
+                    lines: sc.record.span.map(|s| (s.start_line, s.end_line)),
+                    section: sc.record.section.clone(),
+                    symbol: sc.record.symbol.clone(),
                     x: reduced[(i, 0)],
                     y: reduced[(i, 1)],
                 })
                 .collect();
             Ok((
                 StatusCode::OK,
-                Json(serde_json::json!({ "points": points })),
+                Json(serde_json::json!({
+                    "points": points,
+                    "preprocess": chain.names(),
+                    "alignment": alignment,
+                })),
             )
                 .into_response())
         } else {
@@ -321,6 +886,9 @@ async fn api_search(
                     chunk_index: sc.record.chunk_index,
                     score: sc.score,
                     text_preview: preview(&sc.record.text, 160),
+                    lines: sc.record.span.map(|s| (s.start_line, s.end_line)),
+                    section: sc.record.section.clone(),
+                    symbol: sc.record.symbol.clone(),
                     x: reduced[(i, 0)],
                     y: reduced[(i, 1)],
                     z: reduced[(i, 2)],
@@ -328,18 +896,22 @@ async fn api_search(
                 .collect();
             Ok((
                 StatusCode::OK,
-                Json(serde_json::json!({ "points": points })),
+                Json(serde_json::json!({
+                    "points": points,
+                    "preprocess": chain.names(),
+                    "alignment": alignment,
+                })),
             )
                 .into_response())
         }
-    })();
+    })
+    .await;
 
     match res {
         Ok(resp) => resp,
         Err(e) => {
-            info!(error = %e, "search error");
-            let body = serde_json::json!({ "error": e.to_string() });
-            (StatusCode::BAD_REQUEST, Json(body)).into_response()
+            info!(error = %e.message, kind = e.kind, "search error");
+            e.into_response()
         }
     }
 }
@@ -348,19 +920,88 @@ async fn api_search(
 struct IngestTextReq {
     filename: String,
     content: String,
+    // Chunking strategy and params; see `ChunkerConfig` for the defaults
+    #[serde(default = "default_chunker")]
+    chunker: String,
     #[serde(default = "default_tokens_per_chunk")]
     tokens_per_chunk: usize,
     #[serde(default = "default_overlap")]
     overlap: usize,
-    #[serde(default = "default_dim")]
-    dim: usize,
+    #[serde(default = "default_window")]
+    window: usize,
+    #[serde(default = "default_percentile")]
+    percentile: usize,
+    #[serde(default = "default_min_tokens")]
+    min_tokens: usize,
+    // Code chunker language; defaults to the filename's extension
+    #[serde(default)]
+    language: Option<String>,
+    #[serde(default)]
+    gutenberg: bool,
+    #[serde(default)]
+    dim: Option<usize>,
+    #[serde(default)]
+    embedder: Option<String>,
+    #[serde(default)]
+    sublinear_tf: Option<bool>,
+    #[serde(default)]
+    vocabulary: Option<String>,
+    #[serde(default)]
+    ngrams: Option<String>,
+    #[serde(default)]
+    word_weight: Option<f32>,
+    #[serde(default)]
+    vocab_size: Option<usize>,
+    #[serde(default)]
+    pooling: Option<String>,
 }
 
+fn default_chunker() -> String {
+    ChunkerConfig::default().name
+}
 fn default_tokens_per_chunk() -> usize {
-    1000
+    ChunkerConfig::default().tokens_per_chunk
 }
 fn default_overlap() -> usize {
-    300
+    ChunkerConfig::default().overlap
+}
+fn default_window() -> usize {
+    ChunkerConfig::default().window
+}
+
+fn default_percentile() -> usize {
+    ChunkerConfig::default().percentile
+}
+
+fn default_min_tokens() -> usize {
+    ChunkerConfig::default().min_tokens
+}
+
+async fn api_cache_stats(State(state): State<AppState>) -> impl IntoResponse {
+    let stats = state.cache.stats();
+    Json(serde_json::json!({
+        "memory_hits": stats.memory_hits,
+        "store_hits": stats.store_hits,
+        "misses": stats.misses,
+        "entries": stats.entries,
+        "hit_rate": stats.hit_rate(),
+    }))
+}
+
+async fn api_sources(State(state): State<AppState>) -> impl IntoResponse {
+    let res = blocking(move || -> Result<_, ApiError> {
+        let db = Db::open(&state.db_path)?;
+        Ok(db.sources()?)
+    })
+    .await;
+    match res {
+        Ok(sources) => (
+            StatusCode::OK,
+            Json(serde_json::json!({ "sources": sources })),
+        )
+            .into_response(),
+        Err(e) => e.into_response(),
+    }
 }
 
 async fn api_ingest_text(
@@ -368,31 +1009,58 @@ async fn api_ingest_text(
     Json(body): Json<IngestTextReq>,
 ) -> impl IntoResponse {
     let start = std::time::Instant::now();
-    let res = (|| -> Result<_> {
-        let chunks = chunk_by_token_overlap(&body.content, body.tokens_per_chunk, body.overlap);
-        let embedder = HasherEmbedder::new(body.dim);
+    let res = blocking(move || -> Result<_, ApiError> {
         let db = Db::open(&state.db_path)?;
-        for (i, ch) in chunks.iter().enumerate() {
-            let v = embedder.embed(ch);
-            db.insert_chunk(&body.filename, i as i64, ch, &v)?;
-        }
+        let mut embedder = open_embedder(
+            &db,
+            &EmbedderArgs {
+                embedder: body.embedder.clone(),
+                dim: body.dim,
+                sublinear_tf: body.sublinear_tf,
+                vocabulary: body.vocabulary.clone(),
+                ngrams: body.ngrams.clone(),
+                word_weight: body.word_weight,
+                vocab_size: body.vocab_size,
+                pooling: body.pooling.clone(),
+                // File paths and endpoints are only taken from the CLI, never from clients
+                ..EmbedderArgs::default()
+            },
+            &state.cache,
+        )?;
+        let chunking = ChunkerConfig {
+            name: body.chunker.clone(),
+            tokens_per_chunk: body.tokens_per_chunk,
+            overlap: body.overlap,
+            window: body.window,
+            percentile: body.percentile,
+            min_tokens: body.min_tokens,
+            language: body
+                .language
+                .clone()
+                .or_else(|| extension(Path::new(&body.filename))),
+            gutenberg: body.gutenberg,
+        };
+        let chunks = ingest_text(
+            &db,
+            embedder.as_mut(),
+            &chunking,
+            &body.filename,
+            &body.content,
+        )?;
         let took = start.elapsed();
-        info!(filename = %body.filename, chunks = chunks.len(), elapsed_ms = took.as_millis() as u64, "ingest completed");
+        info!(filename = %body.filename, chunks, elapsed_ms = took.as_millis() as u64, "ingest completed");
         Ok(serde_json::json!({
             "filename": body.filename,
-            "chunks": chunks.len(),
+            "chunks": chunks,
             "total_rows": db.count_chunks()?,
         }))
-    })();
+    })
+    .await;
     match res {
         Ok(json) => (StatusCode::OK, Json(json)).into_response(),
         Err(e) => {
-            info!(error = %e, "ingest error");
-            (
-                StatusCode::BAD_REQUEST,
-                Json(serde_json::json!({"error": e.to_string()})),
-            )
-                .into_response()
+            info!(error = %e.message, kind = e.kind, "ingest error");
+            e.into_response()
         }
     }
 }
@@ -0,0 +1,73 @@
+//! Exact search scoring on one in-memory corpus: the per-chunk scalar
+//! `cosine_similarity` loop search used to run, which recomputes both norms
+//! for every pair, against [`CorpusMatrix`], which keeps the rows contiguous
+//! with their norms and scores each with one SIMD dot product.
+//!
+//!     cargo run --release -p umap-core --example cosine_bench -- [rows] [dim] [queries]
+
+use std::time::{Duration, Instant};
+
+use rand::{Rng, SeedableRng, rngs::StdRng};
+use umap_core::CorpusMatrix;
+
+/// `cosine_similarity` as it was before scoring went through SIMD.
+fn scalar_cosine(a: &[f32], b: &[f32]) -> f32 {
+    let mut dot = 0.0f32;
+    let mut na = 0.0f32;
+    let mut nb = 0.0f32;
+    for (x, y) in a.iter().zip(b) {
+        dot += x * y;
+        na += x * x;
+        nb += y * y;
+    }
+    dot / (na.sqrt() * nb.sqrt()).max(1e-8)
+}
+
+fn main() {
+    let mut args = std::env::args()
+        .skip(1)
+        .map(|a| a.parse::<usize>().expect("numeric argument"));
+    let rows = args.next().unwrap_or(50_000);
+    let dim = args.next().unwrap_or(384);
+    let queries = args.next().unwrap_or(10);
+
+    let mut rng = StdRng::seed_from_u64(42);
+    let mut random_vec = || {
+        (0..dim)
+            .map(|_| rng.gen_range(-1.0f32..1.0))
+            .collect::<Vec<_>>()
+    };
+    let corpus: Vec<Vec<f32>> = (0..rows).map(|_| random_vec()).collect();
+    let qs: Vec<Vec<f32>> = (0..queries).map(|_| random_vec()).collect();
+
+    let mut matrix = CorpusMatrix::with_capacity(dim, rows);
+    for row in &corpus {
+        matrix.push(row).unwrap();
+    }
+
+    let mut scalar = Duration::ZERO;
+    let mut batched = Duration::ZERO;
+    let mut max_diff = 0f32;
+    for q in &qs {
+        let start = Instant::now();
+        let expected: Vec<f32> = corpus.iter().map(|row| scalar_cosine(row, q)).collect();
+        scalar += start.elapsed();
+
+        let start = Instant::now();
+        let got = matrix.cosine_scores(q).unwrap();
+        batched += start.elapsed();
+
+        for (e, g) in expected.iter().zip(&got) {
+            max_diff = max_diff.max((e - g).abs());
+        }
+    }
+
+    let per_query = |d: Duration| d.as_secs_f64() * 1e3 / queries as f64;
+    println!("{rows} rows x {dim} dims, {queries} queries");
+    println!("per-chunk scalar loop : {:8.2} ms/query", per_query(scalar));
+    println!(
+        "CorpusMatrix (SIMD)   : {:8.2} ms/query",
+        per_query(batched)
+    );
+    println!("max |score difference|: {max_diff:.2e}");
+}
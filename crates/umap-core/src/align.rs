@@ -0,0 +1,134 @@
+use std::collections::HashMap;
+
+use linfa_linalg::svd::SVD;
+use ndarray::Array2;
+
+use crate::error::{Result, UmapError};
+
+#[derive(Debug, Clone)]
+pub struct ProcrustesResult {
+    /// The full source layout after rotation/reflection, scaling and translation
+    pub layout: Array2<f32>,
+    /// Number of ids shared by both layouts that drove the fit
+    pub matched: usize,
+    /// Mean squared distance between matched points after alignment
+    pub disparity: f32,
+}
+
+/// Align `source` onto `target` with an orthogonal Procrustes fit (rotation,
+/// optional reflection, uniform scale, translation) computed over the rows the
+/// two layouts have in common by id. Every source row is transformed, including
+/// ids the target has never seen.
+///
+/// With fewer than two shared ids there is nothing to fit and the source layout
+/// is returned unchanged with `matched` set accordingly.
+pub fn procrustes_align(
+    source: &Array2<f32>,
+    source_ids: &[i64],
+    target: &Array2<f32>,
+    target_ids: &[i64],
+) -> Result<ProcrustesResult> {
+    let dims = source.ncols();
+    if target.ncols() != dims {
+        return Err(UmapError::DimensionMismatch {
+            expected: dims,
+            actual: target.ncols(),
+        });
+    }
+    if source_ids.len() != source.nrows() || target_ids.len() != target.nrows() {
+        return Err(UmapError::invalid("id count does not match layout rows"));
+    }
+
+    let target_rows: HashMap<i64, usize> = target_ids
+        .iter()
+        .enumerate()
+        .map(|(row, id)| (*id, row))
+        .collect();
+    let pairs: Vec<(usize, usize)> = source_ids
+        .iter()
+        .enumerate()
+        .filter_map(|(row, id)| target_rows.get(id).map(|&t| (row, t)))
+        .collect();
+    let m = pairs.len();
+    if m < 2 {
+        return Ok(ProcrustesResult {
+            layout: source.clone(),
+            matched: m,
+            disparity: 0.0,
+        });
+    }
+
+    // Centroids of the matched subsets
+    let mut mu_a = vec![0f64; dims];
+    let mut mu_b = vec![0f64; dims];
+    for &(s, t) in &pairs {
+        for d in 0..dims {
+            mu_a[d] += source[(s, d)] as f64;
+            mu_b[d] += target[(t, d)] as f64;
+        }
+    }
+    for d in 0..dims {
+        mu_a[d] /= m as f64;
+        mu_b[d] /= m as f64;
+    }
+
+    // Cross-covariance M = A^T B of the centered matched points
+    let mut cov = Array2::<f64>::zeros((dims, dims));
+    let mut norm_a = 0.0f64;
+    for &(s, t) in &pairs {
+        for i in 0..dims {
+            let a = source[(s, i)] as f64 - mu_a[i];
+            norm_a += a * a;
+            for j in 0..dims {
+                cov[(i, j)] += a * (target[(t, j)] as f64 - mu_b[j]);
+            }
+        }
+    }
+    if norm_a <= 1e-12 {
+        // All matched source points coincide; only a translation is meaningful
+        let mut layout = source.clone();
+        for mut row in layout.rows_mut() {
+            for d in 0..dims {
+                row[d] += (mu_b[d] - mu_a[d]) as f32;
+            }
+        }
+        return Ok(ProcrustesResult {
+            layout,
+            matched: m,
+            disparity: 0.0,
+        });
+    }
+
+    // R = U V^T maximizes trace(R^T M); the scale follows from the singular values
+    let (u, sigma, vt) = cov
+        .svd(true, true)
+        .map_err(|e| UmapError::Numerical(format!("Procrustes SVD failed: {e}")))?;
+    let (u, vt) = (u.unwrap(), vt.unwrap());
+    let rot = u.dot(&vt);
+    let scale = sigma.sum() / norm_a;
+
+    let mut layout = Array2::<f32>::zeros(source.dim());
+    for r in 0..source.nrows() {
+        for j in 0..dims {
+            let mut v = 0.0f64;
+            for i in 0..dims {
+                v += (source[(r, i)] as f64 - mu_a[i]) * rot[(i, j)];
+            }
+            layout[(r, j)] = (scale * v + mu_b[j]) as f32;
+        }
+    }
+
+    let mut disparity = 0.0f64;
+    for &(s, t) in &pairs {
+        for d in 0..dims {
+            let diff = (layout[(s, d)] - target[(t, d)]) as f64;
+            disparity += diff * diff;
+        }
+    }
+
+    Ok(ProcrustesResult {
+        layout,
+        matched: m,
+        disparity: (disparity / m as f64) as f32,
+    })
+}
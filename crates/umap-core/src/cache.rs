@@ -0,0 +1,296 @@
+use std::num::NonZeroUsize;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, Mutex};
+
+use lru::LruCache;
+use rusqlite::{Connection, OpenFlags, OptionalExtension, params};
+use serde::Serialize;
+use sha2::{Digest, Sha256};
+
+use crate::db::Db;
+use crate::embedding::{Embedder, EmbedderConfig};
+use crate::error::{Result, UmapError};
+use crate::quantize::{decode_f32, encode_f32};
+
+/// Counters since the cache was created.
+#[derive(Debug, Clone, Copy, Default, Serialize)]
+pub struct CacheStats {
+    pub memory_hits: u64,
+    pub store_hits: u64,
+    pub misses: u64,
+    /// Vectors currently held in memory
+    pub entries: usize,
+}
+
+impl CacheStats {
+    pub fn hits(&self) -> u64 {
+        self.memory_hits + self.store_hits
+    }
+
+    pub fn hit_rate(&self) -> f64 {
+        let total = self.hits() + self.misses;
+        if total == 0 {
+            0.0
+        } else {
+            self.hits() as f64 / total as f64
+        }
+    }
+}
+
+/// Vectors keyed by a SHA-256 of the embedder identity and the text, held in
+/// an in-memory LRU and optionally persisted in an `embedding_cache` table.
+///
+/// One cache can be shared (via `Arc`) by every embedder a process builds, so
+/// a server that constructs an embedder per request still hits it.
+pub struct EmbeddingCache {
+    memory: Option<Mutex<LruCache<String, Vec<f32>>>>,
+    // A connection of its own: `Db` is not `Sync`, embedders must be
+    store: Option<Mutex<Connection>>,
+    memory_hits: AtomicU64,
+    store_hits: AtomicU64,
+    misses: AtomicU64,
+}
+
+impl EmbeddingCache {
+    /// An in-memory cache of up to `capacity` vectors; 0 keeps nothing in memory.
+    pub fn new(capacity: usize) -> Self {
+        Self {
+            memory: NonZeroUsize::new(capacity).map(|c| Mutex::new(LruCache::new(c))),
+            store: None,
+            memory_hits: AtomicU64::new(0),
+            store_hits: AtomicU64::new(0),
+            misses: AtomicU64::new(0),
+        }
+    }
+
+    /// Also persist vectors in the SQLite database at `path`.
+    pub fn with_store(mut self, path: &str) -> Result<Self> {
+        let conn = Connection::open_with_flags(
+            path,
+            OpenFlags::SQLITE_OPEN_READ_WRITE | OpenFlags::SQLITE_OPEN_CREATE,
+        )?;
+        // The store may share a file with a collection that is being ingested
+        conn.busy_timeout(std::time::Duration::from_secs(60))?;
+        conn.execute_batch(
+            r#"
+            CREATE TABLE IF NOT EXISTS embedding_cache (
+                key TEXT PRIMARY KEY,
+                vector BLOB NOT NULL
+            );
+            "#,
+        )?;
+        self.store = Some(Mutex::new(conn));
+        Ok(self)
+    }
+
+    pub fn stats(&self) -> CacheStats {
+        CacheStats {
+            memory_hits: self.memory_hits.load(Ordering::Relaxed),
+            store_hits: self.store_hits.load(Ordering::Relaxed),
+            misses: self.misses.load(Ordering::Relaxed),
+            entries: self
+                .memory
+                .as_ref()
+                .map_or(0, |m| m.lock().unwrap_or_else(|e| e.into_inner()).len()),
+        }
+    }
+
+    /// The cached `dim`-sized vector for `key`. Stored blobs of another
+    /// length count as misses and are overwritten when the vector is put.
+    fn get(&self, key: &str, dim: usize) -> Result<Option<Vec<f32>>> {
+        if let Some(memory) = &self.memory {
+            let mut lru = memory.lock().unwrap_or_else(|e| e.into_inner());
+            if let Some(v) = lru.get(key).filter(|v| v.len() == dim) {
+                self.memory_hits.fetch_add(1, Ordering::Relaxed);
+                return Ok(Some(v.clone()));
+            }
+        }
+        if let Some(store) = &self.store {
+            let conn = store.lock().unwrap_or_else(|e| e.into_inner());
+            let blob: Option<Vec<u8>> = conn
+                .query_row(
+                    "SELECT vector FROM embedding_cache WHERE key = ?1",
+                    params![key],
+                    |row| row.get(0),
+                )
+                .optional()?;
+            if let Some(blob) = blob.filter(|b| b.len() == dim * 4) {
+                let v = decode_f32(&blob);
+                self.store_hits.fetch_add(1, Ordering::Relaxed);
+                self.remember(key, &v);
+                return Ok(Some(v));
+            }
+        }
+        self.misses.fetch_add(1, Ordering::Relaxed);
+        Ok(None)
+    }
+
+    fn remember(&self, key: &str, vector: &[f32]) {
+        if let Some(memory) = &self.memory {
+            memory
+                .lock()
+                .unwrap_or_else(|e| e.into_inner())
+                .put(key.to_string(), vector.to_vec());
+        }
+    }
+
+    fn put_many(&self, entries: &[(String, Vec<f32>)]) -> Result<()> {
+        for (key, vector) in entries {
+            self.remember(key, vector);
+        }
+        if let Some(store) = &self.store {
+            let conn = store.lock().unwrap_or_else(|e| e.into_inner());
+            let tx = conn.unchecked_transaction()?;
+            {
+                let mut stmt = tx.prepare(
+                    "INSERT OR REPLACE INTO embedding_cache (key, vector) VALUES (?1, ?2)",
+                )?;
+                for (key, vector) in entries {
+                    stmt.execute(params![key, encode_f32(vector)])?;
+                }
+            }
+            tx.commit()?;
+        }
+        Ok(())
+    }
+}
+
+/// Wraps an embedder with an [`EmbeddingCache`]. Only texts missing from the
+/// cache reach the inner embedder, in a single batch. Embedders whose vectors
+/// depend on fitted corpus state (see [`Embedder::cacheable`]) pass through.
+pub struct CachedEmbedder {
+    inner: Box<dyn Embedder>,
+    cache: Arc<EmbeddingCache>,
+}
+
+impl CachedEmbedder {
+    pub fn new(inner: Box<dyn Embedder>, cache: Arc<EmbeddingCache>) -> Self {
+        Self { inner, cache }
+    }
+
+    /// Content hash of the embedder identity and the text.
+    fn key(identity: &str, text: &str) -> String {
+        let mut h = Sha256::new();
+        h.update(identity.as_bytes());
+        h.update([0u8]);
+        h.update(text.as_bytes());
+        h.finalize().iter().map(|b| format!("{b:02x}")).collect()
+    }
+}
+
+impl Embedder for CachedEmbedder {
+    fn name(&self) -> &str {
+        self.inner.name()
+    }
+
+    fn dim(&self) -> usize {
+        self.inner.dim()
+    }
+
+    fn version(&self) -> u32 {
+        self.inner.version()
+    }
+
+    fn params(&self) -> serde_json::Value {
+        self.inner.params()
+    }
+
+    fn config(&self) -> EmbedderConfig {
+        self.inner.config()
+    }
+
+    fn cacheable(&self) -> bool {
+        self.inner.cacheable()
+    }
+
+    fn fitted(&self) -> bool {
+        self.inner.fitted()
+    }
+
+    fn fit(&mut self, texts: &[&str]) -> Result<()> {
+        self.inner.fit(texts)
+    }
+
+    fn load_state(&mut self, db: &Db) -> Result<()> {
+        self.inner.load_state(db)
+    }
+
+    fn save_state(&self, db: &Db) -> Result<()> {
+        self.inner.save_state(db)
+    }
+
+    fn embed_batch(&self, texts: &[&str]) -> Result<Vec<Vec<f32>>> {
+        if !self.inner.cacheable() {
+            return self.inner.embed_batch(texts);
+        }
+        let info = self.inner.info();
+        let identity =
+            serde_json::json!([info.name, info.version, info.dim, info.params]).to_string();
+        let keys: Vec<String> = texts.iter().map(|t| Self::key(&identity, t)).collect();
+
+        let mut out: Vec<Option<Vec<f32>>> = Vec::with_capacity(texts.len());
+        let mut missing = Vec::new();
+        for (i, key) in keys.iter().enumerate() {
+            let hit = self.cache.get(key, info.dim)?;
+            if hit.is_none() {
+                missing.push(i);
+            }
+            out.push(hit);
+        }
+        if !missing.is_empty() {
+            let batch: Vec<&str> = missing.iter().map(|&i| texts[i]).collect();
+            let fresh = self.inner.embed_batch(&batch)?;
+            if fresh.len() != batch.len() {
+                return Err(UmapError::Numerical(format!(
+                    "{} returned {} vectors for {} texts",
+                    self.inner.name(),
+                    fresh.len(),
+                    batch.len()
+                )));
+            }
+            let entries: Vec<(String, Vec<f32>)> = missing
+                .iter()
+                .zip(fresh)
+                .map(|(&i, v)| (keys[i].clone(), v))
+                .collect();
+            self.cache.put_many(&entries)?;
+            for (&i, (_, v)) in missing.iter().zip(entries) {
+                out[i] = Some(v);
+            }
+        }
+        Ok(out.into_iter().map(|v| v.unwrap_or_default()).collect())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::embedding::HasherEmbedder;
+
+    #[test]
+    fn stored_blobs_of_the_wrong_length_are_misses() {
+        let path = std::env::temp_dir().join(format!("umap-cache-{}.db", std::process::id()));
+        let cache = Arc::new(
+            EmbeddingCache::new(0)
+                .with_store(path.to_str().unwrap())
+                .unwrap(),
+        );
+        let embedder = CachedEmbedder::new(Box::new(HasherEmbedder::new(8)), cache.clone());
+        let expected = embedder.embed("some text").unwrap();
+
+        // Truncate every stored vector, as a crash or older format might
+        let conn = Connection::open(&path).unwrap();
+        conn.execute(
+            "UPDATE embedding_cache SET vector = substr(vector, 1, 6)",
+            [],
+        )
+        .unwrap();
+        assert_eq!(embedder.embed("some text").unwrap(), expected);
+        let stats = cache.stats();
+        assert_eq!((stats.store_hits, stats.misses), (0, 2));
+        // The bad blob was replaced
+        assert_eq!(embedder.embed("some text").unwrap(), expected);
+        assert_eq!(cache.stats().store_hits, 1);
+        std::fs::remove_file(path).unwrap();
+    }
+}
@@ -0,0 +1,127 @@
+use crate::embedding::{Embedder, EmbedderConfig, fxhash, l2_normalize, signed_hash};
+use crate::error::{Result, UmapError};
+use crate::tokenizer::{Tokenizer, TokenizerConfig, push_char_ngrams};
+
+/// Hashed character n-grams of each word (marked `<word>` so prefixes and
+/// suffixes stay distinct). A typo only disturbs the few n-grams that span it,
+/// so misspelled and OCR-damaged words still land close to the original.
+///
+/// With `word_weight > 0` the vector is the concatenation of an n-gram block
+/// and a word-hashing block, each unit length and scaled so the cosine of two
+/// vectors is `(1 - w) * cos_ngrams + w * cos_words`.
+pub struct CharNgramEmbedder {
+    dim: usize,
+    ngrams: (usize, usize),
+    word_weight: f32,
+    tokenizer: Tokenizer,
+}
+
+impl CharNgramEmbedder {
+    pub fn new(dim: usize, ngrams: (usize, usize), word_weight: f32) -> Result<Self> {
+        let (lo, hi) = ngrams;
+        if lo == 0 || lo > hi {
+            return Err(UmapError::invalid(format!(
+                "invalid n-gram range {lo}-{hi}"
+            )));
+        }
+        if !(0.0..=1.0).contains(&word_weight) {
+            return Err(UmapError::invalid("word_weight must be within 0..=1"));
+        }
+        if word_weight > 0.0 && dim < 2 {
+            return Err(UmapError::invalid(
+                "combining n-grams with words needs dim >= 2",
+            ));
+        }
+        Ok(Self {
+            dim,
+            ngrams,
+            word_weight,
+            tokenizer: Tokenizer::default(),
+        })
+    }
+
+    pub fn with_tokenizer(mut self, config: TokenizerConfig) -> Self {
+        self.tokenizer = Tokenizer::new(config);
+        self
+    }
+
+    /// Size of the n-gram block; the rest (if any) holds word hashes.
+    fn ngram_dim(&self) -> usize {
+        if self.word_weight > 0.0 {
+            self.dim / 2
+        } else {
+            self.dim
+        }
+    }
+
+    fn embed_one(&self, text: &str) -> Vec<f32> {
+        let (lo, hi) = self.ngrams;
+        let mut grams = Vec::new();
+        for word in self.tokenizer.words(text) {
+            push_char_ngrams(&word, lo, hi, &mut grams);
+        }
+        let ngram_block = l2_normalize(hash_into(&grams, self.ngram_dim()));
+        if self.word_weight == 0.0 {
+            return ngram_block;
+        }
+
+        let words = self.tokenizer.tokenize(text);
+        let word_block = l2_normalize(hash_into(&words, self.dim - self.ngram_dim()));
+        let (wc, ww) = ((1.0 - self.word_weight).sqrt(), self.word_weight.sqrt());
+        ngram_block
+            .into_iter()
+            .map(|x| x * wc)
+            .chain(word_block.into_iter().map(|x| x * ww))
+            .collect()
+    }
+}
+
+/// Signed feature hashing of `features` into `dim` buckets.
+fn hash_into(features: &[String], dim: usize) -> Vec<f32> {
+    let mut vec = vec![0f32; dim];
+    for f in features {
+        let (idx, sign) = signed_hash(fxhash(f), dim);
+        vec[idx] += sign;
+    }
+    vec
+}
+
+impl Embedder for CharNgramEmbedder {
+    fn name(&self) -> &str {
+        "charngram"
+    }
+
+    fn dim(&self) -> usize {
+        self.dim
+    }
+
+    // 2: hash signs come from the top bit rather than the bucket's low bit
+    fn version(&self) -> u32 {
+        2
+    }
+
+    fn params(&self) -> serde_json::Value {
+        serde_json::json!({
+            "ngrams": [self.ngrams.0, self.ngrams.1],
+            "word_weight": self.word_weight,
+            "hash": "fnv1a-64",
+            "signed": true,
+            "tokenizer": self.tokenizer.config().describe(),
+        })
+    }
+
+    fn config(&self) -> EmbedderConfig {
+        EmbedderConfig {
+            name: "charngram".to_string(),
+            dim: self.dim,
+            ngrams: self.ngrams,
+            word_weight: self.word_weight,
+            tokenizer: self.tokenizer.config().clone(),
+            ..EmbedderConfig::default()
+        }
+    }
+
+    fn embed_batch(&self, texts: &[&str]) -> Result<Vec<Vec<f32>>> {
+        Ok(texts.iter().map(|t| self.embed_one(t)).collect())
+    }
+}
@@ -1,13 +1,81 @@
-use crate::embedding::tokenize;
+use std::ops::Range;
+use std::sync::LazyLock;
+
+use crate::embedding::Embedder;
+use crate::error::{Result, UmapError};
+use crate::gutenberg::GutenbergChunker;
+use crate::markdown::MarkdownChunker;
+use crate::semantic::SemanticChunker;
+use crate::sentence::sentence_spans;
+use crate::tokenizer::Tokenizer;
+use crate::types::SourceSpan;
 use regex::Regex;
+use serde::{Deserialize, Serialize};
+
+/// Splits a document into chunks. Implementations are selected by name
+/// through [`ChunkerConfig`], and their params are recorded with each source.
+pub trait Chunker: Send + Sync {
+    /// Registry name, e.g. `"token"`.
+    fn name(&self) -> &str;
+    /// Settings that, with the name, reproduce this chunker's output.
+    fn params(&self) -> serde_json::Value;
+    fn chunk(&self, input: &str) -> Result<Vec<Chunk>>;
+}
+
+/// A piece of a document: its verbatim text and where it was cut from.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Chunk {
+    pub text: String,
+    pub span: SourceSpan,
+    /// Heading path of the enclosing section, e.g. "Guide > Install > Linux"
+    pub section: Option<String>,
+    /// Name of the code item the chunk holds, e.g. "impl Display for Span"
+    pub symbol: Option<String>,
+}
+
+/// Line starts of a document, for turning byte offsets into line numbers.
+pub struct LineIndex {
+    starts: Vec<usize>,
+}
+
+impl LineIndex {
+    pub fn new(input: &str) -> Self {
+        let starts = std::iter::once(0)
+            .chain(input.match_indices('\n').map(|(i, _)| i + 1))
+            .collect();
+        Self { starts }
+    }
+
+    /// 1-based line containing byte `offset`.
+    pub fn line(&self, offset: usize) -> usize {
+        self.starts.partition_point(|&s| s <= offset)
+    }
+
+    /// The span of bytes `start..end`, with their first and last line.
+    pub fn span(&self, start: usize, end: usize) -> SourceSpan {
+        SourceSpan {
+            start,
+            end,
+            start_line: self.line(start),
+            end_line: self.line(end.saturating_sub(1).max(start)),
+        }
+    }
+
+    /// The chunk covering `input[start..end]`.
+    pub fn chunk(&self, input: &str, start: usize, end: usize) -> Chunk {
+        Chunk {
+            text: input[start..end].to_string(),
+            span: self.span(start, end),
+            section: None,
+            symbol: None,
+        }
+    }
+}
 
 pub fn split_paragraphs(input: &str) -> Vec<String> {
-    // Split on blank lines as paragraphs
-    let mut paras: Vec<String> = input
-        .split("\n\n")
-        .map(|s| s.trim())
-        .filter(|s| !s.is_empty())
-        .map(|s| s.to_string())
+    let mut paras: Vec<String> = paragraph_spans(input)
+        .into_iter()
+        .map(|r| input[r].to_string())
         .collect();
 
     if paras.is_empty() {
@@ -16,24 +84,35 @@ pub fn split_paragraphs(input: &str) -> Vec<String> {
     paras
 }
 
-pub fn split_sentences(input: &str) -> Vec<String> {
-    // A simple sentence splitter; not perfect but OK for demo
-    let re = Regex::new(r"(?s)(.*?[\.\!\?])\s+").unwrap();
+/// Byte ranges of the paragraphs in `input`, trimmed; paragraphs are separated
+/// by blank (or whitespace-only) lines.
+pub fn paragraph_spans(input: &str) -> Vec<Range<usize>> {
+    static BLANK_LINE: LazyLock<Regex> = LazyLock::new(|| Regex::new(r"\n[ \t\r]*\n").unwrap());
     let mut out = Vec::new();
     let mut last = 0usize;
-    for cap in re.captures_iter(input) {
-        if let Some(m) = cap.get(1) {
-            out.push(m.as_str().trim().to_string());
-            last = m.end();
-        }
-    }
-    let tail = &input[last..];
-    if !tail.trim().is_empty() {
-        out.push(tail.trim().to_string());
+    for m in BLANK_LINE.find_iter(input) {
+        out.extend(trimmed(input, last..m.start()));
+        last = m.end();
     }
+    out.extend(trimmed(input, last..input.len()));
     out
 }
 
+pub fn split_sentences(input: &str) -> Vec<String> {
+    sentence_spans(input)
+        .into_iter()
+        .map(|r| input[r].to_string())
+        .collect()
+}
+
+/// `range` without surrounding whitespace, or `None` if nothing is left.
+pub(crate) fn trimmed(input: &str, range: Range<usize>) -> Option<Range<usize>> {
+    let piece = &input[range.clone()];
+    let start = range.start + (piece.len() - piece.trim_start().len());
+    let end = range.end - (piece.len() - piece.trim_end().len());
+    (start < end).then_some(start..end)
+}
+
 pub fn chunk_by_sentences(sentences: &[String], window: usize) -> Vec<String> {
     if window == 0 {
         return vec![];
@@ -60,11 +139,39 @@ pub fn chunk_text(input: &str) -> Vec<String> {
     chunk_by_sentences(&sents, window)
 }
 
-pub fn chunk_by_token_overlap(input: &str, tokens_per_chunk: usize, overlap: usize) -> Vec<String> {
-    let toks = tokenize(input);
+pub fn chunk_by_token_overlap(input: &str, tokens_per_chunk: usize, overlap: usize) -> Vec<Chunk> {
+    chunk_by_token_overlap_with(&Tokenizer::default(), input, tokens_per_chunk, overlap)
+}
+
+/// Token-window chunking that counts words the way `tokenizer` splits them.
+/// Each chunk runs verbatim from its first word to its last, along with any
+/// punctuation attached to them.
+pub fn chunk_by_token_overlap_with(
+    tokenizer: &Tokenizer,
+    input: &str,
+    tokens_per_chunk: usize,
+    overlap: usize,
+) -> Vec<Chunk> {
+    let lines = LineIndex::new(input);
+    token_windows(tokenizer, input, 0..input.len(), tokens_per_chunk, overlap)
+        .into_iter()
+        .map(|r| lines.chunk(input, r.start, r.end))
+        .collect()
+}
+
+/// Windows of `tokens_per_chunk` words, `overlap` of them shared with the
+/// previous window, over `input[range]`.
+pub(crate) fn token_windows(
+    tokenizer: &Tokenizer,
+    input: &str,
+    range: Range<usize>,
+    tokens_per_chunk: usize,
+    overlap: usize,
+) -> Vec<Range<usize>> {
     if tokens_per_chunk == 0 {
         return vec![];
     }
+    let toks = tokenizer.word_spans(&input[range.clone()]);
     let step = if tokens_per_chunk > overlap {
         tokens_per_chunk - overlap
     } else {
@@ -74,8 +181,9 @@ pub fn chunk_by_token_overlap(input: &str, tokens_per_chunk: usize, overlap: usi
     let mut i = 0usize;
     while i < toks.len() {
         let end = usize::min(i + tokens_per_chunk, toks.len());
-        let chunk = toks[i..end].join(" ");
-        out.push(chunk);
+        let (start, stop) =
+            widen_to_punctuation(&input[range.clone()], toks[i].start, toks[end - 1].end);
+        out.push(range.start + start..range.start + stop);
         if end == toks.len() {
             break;
         }
@@ -83,3 +191,575 @@ pub fn chunk_by_token_overlap(input: &str, tokens_per_chunk: usize, overlap: usi
     }
     out
 }
+
+/// Extend `start..end` over adjacent punctuation (quotes, brackets, a final
+/// period) up to the surrounding whitespace or the next word.
+fn widen_to_punctuation(input: &str, start: usize, end: usize) -> (usize, usize) {
+    let attached = |c: char| !c.is_whitespace() && !c.is_alphanumeric();
+    let start = input[..start]
+        .char_indices()
+        .rev()
+        .take_while(|(_, c)| attached(*c))
+        .last()
+        .map_or(start, |(i, _)| i);
+    let end = end
+        + input[end..]
+            .chars()
+            .take_while(|c| attached(*c))
+            .map(char::len_utf8)
+            .sum::<usize>();
+    (start, end)
+}
+
+/// Byte ranges of the runs of non-whitespace in `input`.
+fn whitespace_runs(input: &str) -> Vec<Range<usize>> {
+    let mut out = Vec::new();
+    let mut start = None;
+    for (i, c) in input.char_indices() {
+        match (c.is_whitespace(), start) {
+            (false, None) => start = Some(i),
+            (true, Some(s)) => {
+                out.push(s..i);
+                start = None;
+            }
+            _ => {}
+        }
+    }
+    out.extend(start.map(|s| s..input.len()));
+    out
+}
+
+/// Byte ranges of the non-blank lines in `input`, without surrounding whitespace.
+#[cfg(feature = "code")]
+fn line_spans(input: &str) -> Vec<Range<usize>> {
+    let mut out = Vec::new();
+    let mut offset = 0;
+    for line in input.split_inclusive('\n') {
+        out.extend(trimmed(input, offset..offset + line.len()));
+        offset += line.len();
+    }
+    out
+}
+
+/// Settings for [`build_chunker`]; each strategy reads only its own fields.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ChunkerConfig {
+    /// Strategy name, one of [`CHUNKER_NAMES`]
+    pub name: String,
+    /// Token, recursive, markdown, semantic: words per chunk (an upper bound
+    /// for all but the first); code: words an item may have before it is
+    /// split at line boundaries
+    #[serde(default = "default_tokens_per_chunk")]
+    pub tokens_per_chunk: usize,
+    /// Token, recursive, markdown: words shared by consecutive chunks (the
+    /// latter two share whole sentences or blocks, up to this many words)
+    #[serde(default = "default_overlap")]
+    pub overlap: usize,
+    /// Sentence: sentences per chunk; semantic: sentences compared on each
+    /// side of a boundary
+    #[serde(default = "default_window")]
+    pub window: usize,
+    /// Semantic: split at boundaries in this lowest percentile of similarity
+    #[serde(default = "default_percentile")]
+    pub percentile: usize,
+    /// Semantic: words a chunk needs before it may end at a topic shift
+    #[serde(default = "default_min_tokens")]
+    pub min_tokens: usize,
+    /// Code: language name or file extension, e.g. "rust" or "py"
+    #[serde(default)]
+    pub language: Option<String>,
+    /// Any strategy: strip Project Gutenberg boilerplate first and record
+    /// chapters (see [`GutenbergChunker`])
+    #[serde(default)]
+    pub gutenberg: bool,
+}
+
+fn default_tokens_per_chunk() -> usize {
+    1000
+}
+
+fn default_overlap() -> usize {
+    300
+}
+
+fn default_window() -> usize {
+    5
+}
+
+fn default_percentile() -> usize {
+    10
+}
+
+fn default_min_tokens() -> usize {
+    100
+}
+
+impl Default for ChunkerConfig {
+    fn default() -> Self {
+        Self {
+            name: "token".to_string(),
+            tokens_per_chunk: default_tokens_per_chunk(),
+            overlap: default_overlap(),
+            window: default_window(),
+            percentile: default_percentile(),
+            min_tokens: default_min_tokens(),
+            language: None,
+            gutenberg: false,
+        }
+    }
+}
+
+/// Names accepted by [`build_chunker`].
+pub const CHUNKER_NAMES: &[&str] = &[
+    "token",
+    "paragraph",
+    "sentence",
+    "recursive",
+    "markdown",
+    "semantic",
+    "code",
+];
+
+/// Build the chunker `config` names. Word counts follow the tokenizer of
+/// `embedder`, so chunk sizes match what it sees; the semantic chunker also
+/// embeds sentences with it.
+pub fn build_chunker<'a>(
+    config: &ChunkerConfig,
+    embedder: &'a dyn Embedder,
+) -> Result<Box<dyn Chunker + 'a>> {
+    let chunker = build_strategy(config, embedder)?;
+    Ok(if config.gutenberg {
+        Box::new(GutenbergChunker::new(chunker))
+    } else {
+        chunker
+    })
+}
+
+fn build_strategy<'a>(
+    config: &ChunkerConfig,
+    embedder: &'a dyn Embedder,
+) -> Result<Box<dyn Chunker + 'a>> {
+    let tokenizer = embedder.config().tokenizer;
+    match config.name.to_lowercase().as_str() {
+        "token" => {
+            if config.tokens_per_chunk == 0 {
+                return Err(UmapError::invalid("tokens_per_chunk must be > 0"));
+            }
+            Ok(Box::new(TokenChunker {
+                tokenizer: Tokenizer::new(tokenizer),
+                tokens_per_chunk: config.tokens_per_chunk,
+                overlap: config.overlap,
+            }))
+        }
+        "paragraph" => Ok(Box::new(ParagraphChunker)),
+        "sentence" => {
+            if config.window == 0 {
+                return Err(UmapError::invalid("sentence window must be > 0"));
+            }
+            Ok(Box::new(SentenceChunker {
+                window: config.window,
+            }))
+        }
+        "recursive" => {
+            if config.tokens_per_chunk == 0 {
+                return Err(UmapError::invalid("tokens_per_chunk must be > 0"));
+            }
+            Ok(Box::new(RecursiveChunker::new(
+                Tokenizer::new(tokenizer),
+                config.tokens_per_chunk,
+                config.overlap,
+            )))
+        }
+        "markdown" => {
+            if config.tokens_per_chunk == 0 {
+                return Err(UmapError::invalid("tokens_per_chunk must be > 0"));
+            }
+            Ok(Box::new(MarkdownChunker::new(
+                Tokenizer::new(tokenizer),
+                config.tokens_per_chunk,
+                config.overlap,
+            )))
+        }
+        "semantic" => {
+            if config.tokens_per_chunk == 0 || config.window == 0 {
+                return Err(UmapError::invalid(
+                    "tokens_per_chunk and window must be > 0",
+                ));
+            }
+            if config.min_tokens > config.tokens_per_chunk {
+                return Err(UmapError::invalid(
+                    "min_tokens must not exceed tokens_per_chunk",
+                ));
+            }
+            Ok(Box::new(SemanticChunker::new(
+                embedder,
+                config.window,
+                config.percentile,
+                config.min_tokens,
+                config.tokens_per_chunk,
+            )))
+        }
+        #[cfg(feature = "code")]
+        "code" => {
+            let language = config.language.as_deref().ok_or_else(|| {
+                UmapError::invalid("code chunker needs a language or a file extension")
+            })?;
+            if config.tokens_per_chunk == 0 {
+                return Err(UmapError::invalid("tokens_per_chunk must be > 0"));
+            }
+            Ok(Box::new(crate::code::CodeChunker::new(
+                language.parse()?,
+                Tokenizer::new(tokenizer),
+                config.tokens_per_chunk,
+            )))
+        }
+        #[cfg(not(feature = "code"))]
+        "code" => Err(UmapError::invalid(
+            "code chunker requires building with the `code` feature",
+        )),
+        other => Err(UmapError::invalid(format!(
+            "unknown chunker: {other} (expected one of {})",
+            CHUNKER_NAMES.join(", ")
+        ))),
+    }
+}
+
+/// Fixed windows of words with overlap; see [`chunk_by_token_overlap_with`].
+pub struct TokenChunker {
+    tokenizer: Tokenizer,
+    tokens_per_chunk: usize,
+    overlap: usize,
+}
+
+impl Chunker for TokenChunker {
+    fn name(&self) -> &str {
+        "token"
+    }
+
+    fn params(&self) -> serde_json::Value {
+        serde_json::json!({
+            "tokens_per_chunk": self.tokens_per_chunk,
+            "overlap": self.overlap,
+            "tokenizer": self.tokenizer.config().describe(),
+        })
+    }
+
+    fn chunk(&self, input: &str) -> Result<Vec<Chunk>> {
+        Ok(chunk_by_token_overlap_with(
+            &self.tokenizer,
+            input,
+            self.tokens_per_chunk,
+            self.overlap,
+        ))
+    }
+}
+
+/// One chunk per blank-line separated paragraph.
+pub struct ParagraphChunker;
+
+impl Chunker for ParagraphChunker {
+    fn name(&self) -> &str {
+        "paragraph"
+    }
+
+    fn params(&self) -> serde_json::Value {
+        serde_json::json!({})
+    }
+
+    fn chunk(&self, input: &str) -> Result<Vec<Chunk>> {
+        let lines = LineIndex::new(input);
+        Ok(paragraph_spans(input)
+            .into_iter()
+            .map(|r| lines.chunk(input, r.start, r.end))
+            .collect())
+    }
+}
+
+/// Consecutive, non-overlapping runs of `window` sentences.
+pub struct SentenceChunker {
+    window: usize,
+}
+
+impl Chunker for SentenceChunker {
+    fn name(&self) -> &str {
+        "sentence"
+    }
+
+    fn params(&self) -> serde_json::Value {
+        serde_json::json!({ "window": self.window })
+    }
+
+    fn chunk(&self, input: &str) -> Result<Vec<Chunk>> {
+        let lines = LineIndex::new(input);
+        Ok(sentence_spans(input)
+            .chunks(self.window)
+            .map(|run| lines.chunk(input, run[0].start, run[run.len() - 1].end))
+            .collect())
+    }
+}
+
+/// LangChain-style recursive splitting: the text is split at the coarsest
+/// level (paragraphs, then sentences, then words) whose pieces fit in
+/// `tokens_per_chunk` words, and adjacent pieces are merged back up to that
+/// budget. Consecutive chunks share up to `overlap` words of whole pieces, so
+/// a chunk only ends mid-sentence when a single sentence exceeds the budget.
+pub struct RecursiveChunker {
+    tokenizer: Tokenizer,
+    tokens_per_chunk: usize,
+    overlap: usize,
+}
+
+#[derive(Clone, Copy)]
+pub(crate) enum Level {
+    Paragraph,
+    Sentence,
+    /// Not part of the prose chain; used for source code
+    #[cfg(feature = "code")]
+    Line,
+    Word,
+}
+
+impl RecursiveChunker {
+    pub(crate) fn new(tokenizer: Tokenizer, tokens_per_chunk: usize, overlap: usize) -> Self {
+        Self {
+            tokenizer,
+            tokens_per_chunk,
+            overlap,
+        }
+    }
+
+    pub(crate) fn tokens_per_chunk(&self) -> usize {
+        self.tokens_per_chunk
+    }
+
+    pub(crate) fn words(&self, input: &str, range: &Range<usize>) -> usize {
+        self.tokenizer.word_spans(&input[range.clone()]).len()
+    }
+
+    /// Chunk ranges covering `input[range]`, each within the word budget.
+    pub(crate) fn split(
+        &self,
+        input: &str,
+        range: Range<usize>,
+        level: Level,
+    ) -> Vec<Range<usize>> {
+        let text = &input[range.clone()];
+        let shift = |r: Range<usize>| range.start + r.start..range.start + r.end;
+        let (pieces, next): (Vec<Range<usize>>, Option<Level>) = match level {
+            Level::Paragraph => (
+                paragraph_spans(text).into_iter().map(shift).collect(),
+                Some(Level::Sentence),
+            ),
+            Level::Sentence => (
+                sentence_spans(text).into_iter().map(shift).collect(),
+                Some(Level::Word),
+            ),
+            #[cfg(feature = "code")]
+            Level::Line => (
+                line_spans(text).into_iter().map(shift).collect(),
+                Some(Level::Word),
+            ),
+            // Whitespace-separated runs rather than tokenizer words, so short
+            // words and stray punctuation between chunks are kept
+            Level::Word => (whitespace_runs(text).into_iter().map(shift).collect(), None),
+        };
+
+        let mut out = Vec::new();
+        let mut fitting: Vec<(Range<usize>, usize)> = Vec::new();
+        for piece in pieces {
+            let n = self.words(input, &piece);
+            match next {
+                Some(next) if n > self.tokens_per_chunk => {
+                    out.extend(self.merge(&fitting));
+                    fitting.clear();
+                    out.extend(self.split(input, piece, next));
+                }
+                _ => fitting.push((piece, n)),
+            }
+        }
+        out.extend(self.merge(&fitting));
+        out
+    }
+
+    /// Join consecutive pieces (with their word counts) up to the budget,
+    /// starting each chunk with the previous one's last pieces up to `overlap`.
+    pub(crate) fn merge(&self, pieces: &[(Range<usize>, usize)]) -> Vec<Range<usize>> {
+        let mut out = Vec::new();
+        let mut current: Vec<(Range<usize>, usize)> = Vec::new();
+        let mut total = 0usize;
+        for (piece, n) in pieces {
+            if total + n > self.tokens_per_chunk && !current.is_empty() {
+                out.push(current[0].0.start..current[current.len() - 1].0.end);
+                // Pieces without words (stray punctuation) never carry over
+                // on their own
+                while !current.is_empty()
+                    && (total > self.overlap || total + n > self.tokens_per_chunk || total == 0)
+                {
+                    total -= current.remove(0).1;
+                }
+            }
+            current.push((piece.clone(), *n));
+            total += n;
+        }
+        if !current.is_empty() {
+            out.push(current[0].0.start..current[current.len() - 1].0.end);
+        }
+        out
+    }
+}
+
+impl Chunker for RecursiveChunker {
+    fn name(&self) -> &str {
+        "recursive"
+    }
+
+    fn params(&self) -> serde_json::Value {
+        serde_json::json!({
+            "tokens_per_chunk": self.tokens_per_chunk,
+            "overlap": self.overlap,
+            "tokenizer": self.tokenizer.config().describe(),
+        })
+    }
+
+    fn chunk(&self, input: &str) -> Result<Vec<Chunk>> {
+        let lines = LineIndex::new(input);
+        Ok(self
+            .split(input, 0..input.len(), Level::Paragraph)
+            .into_iter()
+            .map(|r| lines.chunk(input, r.start, r.end))
+            .collect())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use rand::prelude::*;
+
+    use super::*;
+
+    const WORDS: &[&str] = &[
+        "the",
+        "a",
+        "I",
+        "river",
+        "ran",
+        "quickly",
+        "past",
+        "Mr.",
+        "Smith's",
+        "e.g.",
+        "3.14",
+        "well-known",
+        "(aside)",
+        "\"quoted\"",
+        "—",
+        "naïve",
+        "東京",
+        "x",
+        "end,",
+        "so;",
+    ];
+
+    /// Paragraphs of sentences of random words, with assorted whitespace.
+    fn random_text(rng: &mut StdRng) -> String {
+        let mut text = String::new();
+        for p in 0..rng.gen_range(1..6) {
+            if p > 0 {
+                text.push_str(["\n\n", "\n \n", "\n\n\n"].choose(rng).unwrap());
+            }
+            for s in 0..rng.gen_range(1..6) {
+                if s > 0 {
+                    text.push_str([" ", "  ", "\n"].choose(rng).unwrap());
+                }
+                let words: Vec<&str> = (0..rng.gen_range(1..30))
+                    .map(|_| *WORDS.choose(rng).unwrap())
+                    .collect();
+                text.push_str(&words.join(" "));
+                text.push_str([".", "!", "?", "..."].choose(rng).unwrap());
+            }
+        }
+        text
+    }
+
+    fn stripped(s: &str) -> String {
+        s.chars().filter(|c| !c.is_whitespace()).collect()
+    }
+
+    /// Largest word count of a single whitespace-separated piece.
+    fn largest_piece(chunker: &RecursiveChunker, text: &str) -> usize {
+        let mut at = 0;
+        text.split_whitespace()
+            .map(|piece| {
+                let start = at + text[at..].find(piece).unwrap();
+                at = start + piece.len();
+                chunker.words(text, &(start..at))
+            })
+            .max()
+            .unwrap_or(0)
+    }
+
+    fn spans(chunker: &RecursiveChunker, text: &str) -> Vec<Range<usize>> {
+        chunker
+            .chunk(text)
+            .unwrap()
+            .into_iter()
+            .map(|c| {
+                assert_eq!(c.text, text[c.span.start..c.span.end]);
+                c.span.start..c.span.end
+            })
+            .collect()
+    }
+
+    #[test]
+    fn recursive_chunks_fit_the_budget() {
+        let mut rng = StdRng::seed_from_u64(45);
+        for _ in 0..500 {
+            let text = random_text(&mut rng);
+            let budget = rng.gen_range(1..40);
+            let overlap = rng.gen_range(0..=budget);
+            let chunker = RecursiveChunker::new(Tokenizer::default(), budget, overlap);
+            if largest_piece(&chunker, &text) > budget {
+                continue;
+            }
+            for span in spans(&chunker, &text) {
+                let n = chunker.words(&text, &span);
+                assert!(n <= budget, "{n} words > {budget} in {:?}", &text[span]);
+            }
+        }
+    }
+
+    #[test]
+    fn recursive_chunks_without_overlap_rebuild_the_input() {
+        let mut rng = StdRng::seed_from_u64(46);
+        for _ in 0..500 {
+            let text = random_text(&mut rng);
+            let chunker = RecursiveChunker::new(Tokenizer::default(), rng.gen_range(1..40), 0);
+            let spans = spans(&chunker, &text);
+            for pair in spans.windows(2) {
+                assert!(pair[0].end <= pair[1].start, "{pair:?} in {text:?}");
+            }
+            let rebuilt: String = spans.iter().map(|s| stripped(&text[s.clone()])).collect();
+            assert_eq!(rebuilt, stripped(&text), "{spans:?} of {text:?}");
+        }
+    }
+
+    #[test]
+    fn recursive_chunks_with_overlap_leave_no_gaps() {
+        let mut rng = StdRng::seed_from_u64(47);
+        for _ in 0..500 {
+            let text = random_text(&mut rng);
+            let budget = rng.gen_range(2..40);
+            let chunker =
+                RecursiveChunker::new(Tokenizer::default(), budget, rng.gen_range(1..budget));
+            let spans = spans(&chunker, &text);
+            for pair in spans.windows(2) {
+                assert!(pair[1].start > pair[0].start, "{pair:?} in {text:?}");
+                let gap = &text[pair[0].end.min(pair[1].start)..pair[1].start];
+                assert!(
+                    pair[1].start <= pair[0].end || gap.trim().is_empty(),
+                    "{pair:?} skips {gap:?}"
+                );
+            }
+        }
+    }
+}
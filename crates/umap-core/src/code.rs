@@ -0,0 +1,393 @@
+use std::ops::Range;
+use std::str::FromStr;
+
+use tree_sitter::{Language, Node, Parser};
+
+use crate::chunk::{Chunk, Chunker, Level, LineIndex, RecursiveChunker};
+use crate::error::{Result, UmapError};
+use crate::tokenizer::Tokenizer;
+
+/// Grammars the code chunker can parse.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CodeLanguage {
+    Rust,
+    Python,
+    TypeScript,
+    Tsx,
+}
+
+impl FromStr for CodeLanguage {
+    type Err = UmapError;
+
+    /// A language name or a file extension.
+    fn from_str(s: &str) -> Result<Self> {
+        match s.to_lowercase().as_str() {
+            "rust" | "rs" => Ok(CodeLanguage::Rust),
+            "python" | "py" | "pyi" => Ok(CodeLanguage::Python),
+            "typescript" | "ts" | "mts" | "cts" => Ok(CodeLanguage::TypeScript),
+            "tsx" => Ok(CodeLanguage::Tsx),
+            other => Err(UmapError::invalid(format!(
+                "unsupported code language: {other} (expected rust, python, typescript or tsx)"
+            ))),
+        }
+    }
+}
+
+impl CodeLanguage {
+    pub fn name(self) -> &'static str {
+        match self {
+            CodeLanguage::Rust => "rust",
+            CodeLanguage::Python => "python",
+            CodeLanguage::TypeScript => "typescript",
+            CodeLanguage::Tsx => "tsx",
+        }
+    }
+
+    fn grammar(self) -> Language {
+        match self {
+            CodeLanguage::Rust => tree_sitter_rust::LANGUAGE.into(),
+            CodeLanguage::Python => tree_sitter_python::LANGUAGE.into(),
+            CodeLanguage::TypeScript => tree_sitter_typescript::LANGUAGE_TYPESCRIPT.into(),
+            CodeLanguage::Tsx => tree_sitter_typescript::LANGUAGE_TSX.into(),
+        }
+    }
+
+    /// Comments and attributes that belong to the item after them. Inner
+    /// doc comments (`//!`, `/*!`) document the enclosing module instead.
+    fn is_leading(self, node: Node, src: &[u8]) -> bool {
+        match (self, node.kind()) {
+            (CodeLanguage::Rust, "line_comment" | "block_comment") => {
+                let text = &src[node.start_byte()..node.end_byte()];
+                !(text.starts_with(b"//!") || text.starts_with(b"/*!"))
+            }
+            (CodeLanguage::Rust, "attribute_item") => true,
+            (CodeLanguage::Rust, _) => false,
+            (_, kind) => kind == "comment",
+        }
+    }
+
+    /// Symbol name of a top-level item, or `None` for statements that are not
+    /// items (imports, expressions, ...).
+    fn symbol(self, node: Node, src: &[u8]) -> Option<String> {
+        let field = |node: Node, name: &str| {
+            node.child_by_field_name(name)
+                .and_then(|n| n.utf8_text(src).ok())
+                .map(str::to_string)
+        };
+        match (self, node.kind()) {
+            (CodeLanguage::Rust, "impl_item") => {
+                let ty = field(node, "type")?;
+                Some(match field(node, "trait") {
+                    Some(tr) => format!("impl {tr} for {ty}"),
+                    None => format!("impl {ty}"),
+                })
+            }
+            (
+                CodeLanguage::Rust,
+                "function_item" | "struct_item" | "enum_item" | "union_item" | "trait_item"
+                | "mod_item" | "const_item" | "static_item" | "type_item" | "macro_definition",
+            ) => field(node, "name"),
+            (CodeLanguage::Python, "function_definition" | "class_definition") => {
+                field(node, "name")
+            }
+            (CodeLanguage::Python, "decorated_definition") => {
+                self.symbol(node.child_by_field_name("definition")?, src)
+            }
+            (
+                CodeLanguage::TypeScript | CodeLanguage::Tsx,
+                "function_declaration"
+                | "generator_function_declaration"
+                | "class_declaration"
+                | "abstract_class_declaration"
+                | "interface_declaration"
+                | "type_alias_declaration"
+                | "enum_declaration"
+                | "internal_module"
+                | "module",
+            ) => field(node, "name"),
+            (CodeLanguage::TypeScript | CodeLanguage::Tsx, "lexical_declaration") => {
+                let mut cursor = node.walk();
+                let first = node
+                    .named_children(&mut cursor)
+                    .find(|n| n.kind() == "variable_declarator")?;
+                field(first, "name")
+            }
+            (CodeLanguage::TypeScript | CodeLanguage::Tsx, "export_statement") => {
+                self.symbol(node.child_by_field_name("declaration")?, src)
+            }
+            _ => None,
+        }
+    }
+}
+
+/// One chunk per top-level item of a source file (function, type, impl,
+/// class, ...), with the comments and attributes directly above it; a blank
+/// line detaches them. Statements between items, such as imports, and
+/// detached comments are grouped into chunks of their own without a symbol.
+/// An item over `tokens_per_chunk` words is split at line boundaries, every
+/// piece keeping its symbol.
+pub struct CodeChunker {
+    language: CodeLanguage,
+    inner: RecursiveChunker,
+}
+
+impl CodeChunker {
+    pub fn new(language: CodeLanguage, tokenizer: Tokenizer, tokens_per_chunk: usize) -> Self {
+        Self {
+            language,
+            inner: RecursiveChunker::new(tokenizer, tokens_per_chunk, 0),
+        }
+    }
+
+    /// Top-level pieces of `input` with their symbol names.
+    fn items(&self, input: &str) -> Vec<(Range<usize>, Option<String>)> {
+        let mut parser = Parser::new();
+        // Grammars are compiled in, so their ABI always matches
+        let tree = parser
+            .set_language(&self.language.grammar())
+            .ok()
+            .and_then(|_| parser.parse(input, None));
+        let Some(tree) = tree else {
+            return vec![(0..input.len(), None)];
+        };
+
+        let src = input.as_bytes();
+        let root = tree.root_node();
+        let mut cursor = root.walk();
+        let mut out: Vec<(Range<usize>, Option<String>)> = Vec::new();
+        // Comments/attributes waiting for the item they document
+        let mut lead: Option<Range<usize>> = None;
+        // Last row of the previous leading node
+        let mut lead_row = 0;
+        for node in root.named_children(&mut cursor) {
+            // Anything after a blank line no longer belongs to the lead
+            if node.start_position().row > lead_row + 1
+                && let Some(detached) = lead.take()
+            {
+                push_loose(&mut out, detached);
+            }
+            if self.language.is_leading(node, src) {
+                lead.get_or_insert(node.start_byte()..0).end = node.end_byte();
+                lead_row = last_row(node);
+                continue;
+            }
+            let start = lead.take().map_or(node.start_byte(), |l| l.start);
+            let range = start..node.end_byte();
+            match self.language.symbol(node, src) {
+                Some(name) => out.push((range, Some(name))),
+                None => push_loose(&mut out, range),
+            }
+        }
+        if let Some(trailing) = lead {
+            push_loose(&mut out, trailing);
+        }
+        out
+    }
+}
+
+/// Row of the last character of `node`; line comments end after their newline.
+fn last_row(node: Node) -> usize {
+    let end = node.end_position();
+    if end.column == 0 && end.row > node.start_position().row {
+        end.row - 1
+    } else {
+        end.row
+    }
+}
+
+/// Add non-item code, joining it to directly preceding non-item code.
+fn push_loose(out: &mut Vec<(Range<usize>, Option<String>)>, range: Range<usize>) {
+    match out.last_mut() {
+        Some((last, None)) => last.end = range.end,
+        _ => out.push((range, None)),
+    }
+}
+
+impl Chunker for CodeChunker {
+    fn name(&self) -> &str {
+        "code"
+    }
+
+    fn params(&self) -> serde_json::Value {
+        let mut params = self.inner.params();
+        params["language"] = self.language.name().into();
+        params
+    }
+
+    fn chunk(&self, input: &str) -> Result<Vec<Chunk>> {
+        if input.trim().is_empty() {
+            return Ok(Vec::new());
+        }
+        let lines = LineIndex::new(input);
+        let mut out = Vec::new();
+        for (range, symbol) in self.items(input) {
+            let pieces = if self.inner.words(input, &range) > self.inner.tokens_per_chunk() {
+                self.inner.split(input, range, Level::Line)
+            } else {
+                vec![range]
+            };
+            out.extend(pieces.into_iter().map(|r| Chunk {
+                symbol: symbol.clone(),
+                ..lines.chunk(input, r.start, r.end)
+            }));
+        }
+        Ok(out)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn chunks(language: CodeLanguage, tokens_per_chunk: usize, input: &str) -> Vec<Chunk> {
+        CodeChunker::new(language, Tokenizer::default(), tokens_per_chunk)
+            .chunk(input)
+            .unwrap()
+    }
+
+    fn symbols(language: CodeLanguage, input: &str) -> Vec<Option<String>> {
+        chunks(language, 1000, input)
+            .into_iter()
+            .map(|c| c.symbol)
+            .collect()
+    }
+
+    fn pieces(language: CodeLanguage, input: &str) -> Vec<(Option<String>, String)> {
+        chunks(language, 1000, input)
+            .into_iter()
+            .map(|c| (c.symbol, c.text))
+            .collect()
+    }
+
+    fn some(names: &[&str]) -> Vec<Option<String>> {
+        names.iter().map(|n| Some(n.to_string())).collect()
+    }
+
+    #[test]
+    fn one_symbol_per_item() {
+        let rust = "struct Span;\n\
+                    enum Kind { A }\n\
+                    impl Span { fn new() -> Self { Span } }\n\
+                    impl std::fmt::Display for Span {}\n\
+                    trait Render {}\n\
+                    mod inner {}\n\
+                    const LIMIT: usize = 3;\n\
+                    macro_rules! twice { () => {} }\n\
+                    fn main() {}\n";
+        assert_eq!(
+            symbols(CodeLanguage::Rust, rust),
+            some(&[
+                "Span",
+                "Kind",
+                "impl Span",
+                "impl std::fmt::Display for Span",
+                "Render",
+                "inner",
+                "LIMIT",
+                "twice",
+                "main",
+            ])
+        );
+
+        let python = "def load(path):\n    return path\n\n\
+                      class Store:\n    pass\n\n\
+                      @cached\ndef fetch():\n    pass\n";
+        assert_eq!(
+            symbols(CodeLanguage::Python, python),
+            some(&["load", "Store", "fetch"])
+        );
+
+        let ts = "function parse(s: string) { return s; }\n\
+                  class Parser {}\n\
+                  interface Options { strict: boolean }\n\
+                  type Id = string;\n\
+                  enum Mode { Fast }\n\
+                  const limit = 3;\n\
+                  export function run() {}\n";
+        assert_eq!(
+            symbols(CodeLanguage::TypeScript, ts),
+            some(&["parse", "Parser", "Options", "Id", "Mode", "limit", "run"])
+        );
+    }
+
+    #[test]
+    fn attaches_only_comments_directly_above() {
+        let rust = "//! Crate docs.\n\
+                    // Licensed under MIT.\n\
+                    \n\
+                    use std::fmt;\n\
+                    \n\
+                    /// A span.\n\
+                    #[derive(Debug)]\n\
+                    struct Span;\n\
+                    \n\
+                    // Unrelated note.\n\
+                    \n\
+                    fn main() {}\n";
+        assert_eq!(
+            pieces(CodeLanguage::Rust, rust),
+            vec![
+                (
+                    None,
+                    "//! Crate docs.\n// Licensed under MIT.\n\nuse std::fmt;".to_string()
+                ),
+                (
+                    Some("Span".to_string()),
+                    "/// A span.\n#[derive(Debug)]\nstruct Span;".to_string()
+                ),
+                (None, "// Unrelated note.".to_string()),
+                (Some("main".to_string()), "fn main() {}".to_string()),
+            ]
+        );
+
+        let rust = "/*! Module docs. */\nfn main() {}\n";
+        assert_eq!(
+            pieces(CodeLanguage::Rust, rust),
+            vec![
+                (None, "/*! Module docs. */".to_string()),
+                (Some("main".to_string()), "fn main() {}".to_string()),
+            ]
+        );
+
+        let python = "# Copyright notice\n\nimport os\n\n# Reads it.\ndef load():\n    pass\n";
+        assert_eq!(
+            pieces(CodeLanguage::Python, python),
+            vec![
+                (None, "# Copyright notice\n\nimport os".to_string()),
+                (
+                    Some("load".to_string()),
+                    "# Reads it.\ndef load():\n    pass".to_string()
+                ),
+            ]
+        );
+    }
+
+    #[test]
+    fn splits_items_over_budget_at_lines() {
+        let body: String = (0..30)
+            .map(|i| format!("    let value{i} = compute(input, {i});\n"))
+            .collect();
+        let input =
+            format!("use std::io;\n\nfn long(input: u32) {{\n{body}}}\n\nfn short() {{}}\n");
+        let got = chunks(CodeLanguage::Rust, 20, &input);
+        let long: Vec<&Chunk> = got
+            .iter()
+            .filter(|c| c.symbol.as_deref() == Some("long"))
+            .collect();
+        assert!(long.len() > 1, "{got:?}");
+        let tokenizer = Tokenizer::default();
+        for c in &long {
+            assert!(tokenizer.word_spans(&c.text).len() <= 20, "{c:?}");
+            assert!(!c.text.starts_with(' ') && !c.text.ends_with('\n'));
+        }
+        assert!(long[0].text.starts_with("fn long"));
+        assert!(long[long.len() - 1].text.ends_with('}'));
+        for pair in long.windows(2) {
+            assert!(pair[0].span.end < pair[1].span.start);
+        }
+        assert_eq!(got.last().unwrap().text, "fn short() {}");
+        for c in &got {
+            assert_eq!(&input[c.span.start..c.span.end], c.text);
+        }
+    }
+}
@@ -1,11 +1,26 @@
-use anyhow::Result;
-use rusqlite::{Connection, OpenFlags, params};
+use std::cell::RefCell;
+use std::time::Duration;
+
+use rusqlite::{Connection, OpenFlags, OptionalExtension, params};
 use time::OffsetDateTime;
 
-use crate::types::ChunkRecord;
+use crate::chunk::Chunk;
+use crate::embedding::EmbedderInfo;
+use crate::error::{Result, UmapError};
+use crate::quantize::{Quantization, VectorStorage, encode_f32};
+use crate::scoring;
+use crate::types::{ChunkRecord, SourceRecord, SourceSpan, TermStat};
+
+const EMBEDDER_KEY: &str = "embedder";
+const STORAGE_KEY: &str = "vector_storage";
+// How long a writer waits for another connection's write transaction
+const BUSY_TIMEOUT: Duration = Duration::from_secs(60);
 
 pub struct Db {
     conn: Connection,
+    // Cached collection embedder record; only populated once one is stored
+    embedder: RefCell<Option<EmbedderInfo>>,
+    storage: RefCell<Option<VectorStorage>>,
 }
 
 impl Db {
@@ -14,15 +29,26 @@ impl Db {
             path,
             OpenFlags::SQLITE_OPEN_READ_WRITE | OpenFlags::SQLITE_OPEN_CREATE,
         )?;
-        let db = Self { conn };
+        conn.busy_timeout(BUSY_TIMEOUT)?;
+        let db = Self {
+            conn,
+            embedder: RefCell::new(None),
+            storage: RefCell::new(None),
+        };
         db.init()?;
         Ok(db)
     }
 
     fn init(&self) -> Result<()> {
+        self.conn.execute_batch("PRAGMA journal_mode=WAL")?;
+        // Connections opening a new or older database at once would race to
+        // create and migrate it
+        self.write_transaction(|| self.create_schema())
+    }
+
+    fn create_schema(&self) -> Result<()> {
         self.conn.execute_batch(
             r#"
-            PRAGMA journal_mode=WAL;
             CREATE TABLE IF NOT EXISTS chunks (
                 id INTEGER PRIMARY KEY,
                 source TEXT NOT NULL,
@@ -34,69 +60,372 @@ impl Db {
             );
             CREATE INDEX IF NOT EXISTS idx_chunks_source ON chunks(source);
             CREATE INDEX IF NOT EXISTS idx_chunks_chunk_index ON chunks(chunk_index);
+            CREATE TABLE IF NOT EXISTS collection_meta (
+                key TEXT PRIMARY KEY,
+                value TEXT NOT NULL
+            );
+            CREATE TABLE IF NOT EXISTS embedder_state (
+                key TEXT PRIMARY KEY,
+                value BLOB NOT NULL
+            );
+            CREATE TABLE IF NOT EXISTS sources (
+                source TEXT PRIMARY KEY,
+                chunker TEXT NOT NULL,
+                params TEXT NOT NULL,
+                chunks INTEGER NOT NULL,
+                ingested_at TEXT NOT NULL
+            );
+            CREATE TABLE IF NOT EXISTS tfidf_terms (
+                term TEXT PRIMARY KEY,
+                df INTEGER NOT NULL,
+                idx INTEGER
+            );
             "#,
         )?;
+        // Columns added after the original schema; older databases are
+        // migrated in place and their existing rows keep NULLs
+        for (column, ty) in [
+            // Full-precision copies for quantized collections
+            ("vector_full", "BLOB"),
+            // Source span: byte offsets and 1-based lines
+            ("start_offset", "INTEGER"),
+            ("end_offset", "INTEGER"),
+            ("start_line", "INTEGER"),
+            ("end_line", "INTEGER"),
+            // Heading path from section-aware chunkers
+            ("section", "TEXT"),
+            // Item name from the code chunker
+            ("symbol", "TEXT"),
+            // L2 norm of the f32 vector, so exact search need not recompute it
+            ("norm", "REAL"),
+        ] {
+            if !self.has_column("chunks", column)? {
+                self.conn
+                    .execute_batch(&format!("ALTER TABLE chunks ADD COLUMN {column} {ty}"))?;
+            }
+        }
         Ok(())
     }
 
+    fn has_column(&self, table: &str, column: &str) -> Result<bool> {
+        let mut stmt = self.conn.prepare(&format!("PRAGMA table_info({table})"))?;
+        let names = stmt
+            .query_map([], |row| row.get::<_, String>(1))?
+            .collect::<std::result::Result<Vec<_>, _>>()?;
+        Ok(names.iter().any(|n| n == column))
+    }
+
     pub fn insert_chunk(
         &self,
         source: &str,
         chunk_index: i64,
-        text: &str,
+        chunk: &Chunk,
         vector: &[f32],
     ) -> Result<i64> {
+        if let Some(info) = self.embedder_info()?
+            && vector.len() != info.dim
+        {
+            return Err(UmapError::DimensionMismatch {
+                expected: info.dim,
+                actual: vector.len(),
+            });
+        }
         let created_at = OffsetDateTime::now_utc()
             .format(&time::format_description::well_known::Rfc3339)
             .unwrap();
         let dim = vector.len() as i64;
+        let storage = self.vector_storage()?;
         let mut stmt = self.conn.prepare(
             r#"
-            INSERT INTO chunks (source, chunk_index, text, dim, vector, created_at)
-            VALUES (?1, ?2, ?3, ?4, ?5, ?6)
+            INSERT INTO chunks (source, chunk_index, text, dim, vector, vector_full, created_at,
+                                start_offset, end_offset, start_line, end_line, section,
+                                symbol, norm)
+            VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11, ?12, ?13, ?14)
             "#,
         )?;
-        let mut blob: Vec<u8> = Vec::with_capacity(vector.len() * 4);
-        for v in vector {
-            blob.extend_from_slice(&v.to_le_bytes());
-        }
-        stmt.execute(params![source, chunk_index, text, dim, blob, created_at])?;
+        let blob = storage.quantization.encode(vector);
+        let full = (storage.keep_full_precision && storage.quantization != Quantization::None)
+            .then(|| encode_f32(vector));
+        stmt.execute(params![
+            source,
+            chunk_index,
+            chunk.text,
+            dim,
+            blob,
+            full,
+            created_at,
+            chunk.span.start as i64,
+            chunk.span.end as i64,
+            chunk.span.start_line as i64,
+            chunk.span.end_line as i64,
+            chunk.section,
+            chunk.symbol,
+            scoring::norm(vector) as f64,
+        ])?;
         Ok(self.conn.last_insert_rowid())
     }
 
+    /// Every chunk, with quantized vectors decoded to approximate f32 ones
+    /// (or the full-precision copies when the collection keeps them).
     pub fn all_chunks(&self) -> Result<Vec<ChunkRecord>> {
         let mut stmt = self.conn.prepare(
-            r#"SELECT id, source, chunk_index, text, dim, vector FROM chunks ORDER BY id"#,
+            r#"SELECT id, source, chunk_index, text, dim, vector, vector_full, start_offset, end_offset, start_line, end_line, section, symbol FROM chunks ORDER BY id"#,
         )?;
-        let rows = stmt.query_map([], |row| {
+        self.read_chunks(&mut stmt, [])
+    }
+
+    /// The chunks with the given ids, in that order; unknown ids are skipped.
+    pub fn chunks_by_ids(&self, ids: &[i64]) -> Result<Vec<ChunkRecord>> {
+        let mut stmt = self.conn.prepare_cached(
+            r#"SELECT id, source, chunk_index, text, dim, vector, vector_full, start_offset, end_offset, start_line, end_line, section, symbol FROM chunks WHERE id = ?1"#,
+        )?;
+        let mut out = Vec::with_capacity(ids.len());
+        for id in ids {
+            out.extend(self.read_chunks(&mut stmt, params![id])?);
+        }
+        Ok(out)
+    }
+
+    fn read_chunks<P: rusqlite::Params>(
+        &self,
+        stmt: &mut rusqlite::Statement<'_>,
+        params: P,
+    ) -> Result<Vec<ChunkRecord>> {
+        let quantization = self.vector_storage()?.quantization;
+        let rows = stmt.query_map(params, |row| {
             let id: i64 = row.get(0)?;
             let source: String = row.get(1)?;
             let chunk_index: i64 = row.get(2)?;
             let text: String = row.get(3)?;
             let dim: i64 = row.get(4)?;
             let blob: Vec<u8> = row.get(5)?;
-            let expected = (dim as usize) * 4;
-            if blob.len() != expected {
-                let err =
-                    std::io::Error::new(std::io::ErrorKind::InvalidData, "vector length mismatch");
-                return Err(rusqlite::Error::FromSqlConversionFailure(
-                    5,
-                    rusqlite::types::Type::Blob,
-                    Box::new(err),
-                ));
-            }
-            let mut vec = vec![0f32; dim as usize];
-            for (i, item) in vec.iter_mut().enumerate().take(dim as usize) {
-                let j = i * 4;
-                *item = f32::from_le_bytes([blob[j], blob[j + 1], blob[j + 2], blob[j + 3]]);
-            }
-            Ok(ChunkRecord {
+            let full: Option<Vec<u8>> = row.get(6)?;
+            let offsets: [Option<i64>; 4] = [row.get(7)?, row.get(8)?, row.get(9)?, row.get(10)?];
+            let span = match offsets {
+                [Some(start), Some(end), Some(start_line), Some(end_line)] => Some(SourceSpan {
+                    start: start as usize,
+                    end: end as usize,
+                    start_line: start_line as usize,
+                    end_line: end_line as usize,
+                }),
+                _ => None,
+            };
+            let section: Option<String> = row.get(11)?;
+            let symbol: Option<String> = row.get(12)?;
+            Ok((
+                id,
+                source,
+                chunk_index,
+                text,
+                dim,
+                blob,
+                full,
+                span,
+                section,
+                symbol,
+            ))
+        })?;
+        let mut out = Vec::new();
+        for r in rows {
+            let (id, source, chunk_index, text, dim, blob, full, span, section, symbol) = r?;
+            let vector = match full {
+                Some(full) => decode_vector(Quantization::None, id, dim, &full)?,
+                None => decode_vector(quantization, id, dim, &blob)?,
+            };
+            out.push(ChunkRecord {
                 id,
                 source,
                 chunk_index,
                 text,
                 dim: dim as usize,
-                vector: vec,
+                vector,
+                span,
+                section,
+                symbol,
+            });
+        }
+        Ok(out)
+    }
+
+    /// Visit the stored (possibly quantized) vector of every chunk without
+    /// loading texts; search scans these.
+    pub fn for_each_vector(
+        &self,
+        mut f: impl FnMut(i64, usize, &[u8]) -> Result<()>,
+    ) -> Result<()> {
+        self.for_each_vector_with_norm(|id, dim, blob, _| f(id, dim, blob))
+    }
+
+    /// Like [`Db::for_each_vector`], also passing each vector's norm as stored
+    /// at insert; `None` for rows written before norms were kept.
+    pub fn for_each_vector_with_norm(
+        &self,
+        mut f: impl FnMut(i64, usize, &[u8], Option<f32>) -> Result<()>,
+    ) -> Result<()> {
+        let quantization = self.vector_storage()?.quantization;
+        let mut stmt = self
+            .conn
+            .prepare("SELECT id, dim, vector, norm FROM chunks ORDER BY id")?;
+        let mut rows = stmt.query([])?;
+        while let Some(row) = rows.next()? {
+            let id: i64 = row.get(0)?;
+            let dim: i64 = row.get(1)?;
+            let norm: Option<f64> = row.get(3)?;
+            let blob = row.get_ref(2)?.as_blob().map_err(rusqlite::Error::from)?;
+            check_vector_len(quantization, id, dim, blob)?;
+            f(id, dim as usize, blob, norm.map(|n| n as f32))?;
+        }
+        Ok(())
+    }
+
+    /// How this collection stores vectors; plain f32 unless recorded otherwise.
+    pub fn vector_storage(&self) -> Result<VectorStorage> {
+        if let Some(storage) = *self.storage.borrow() {
+            return Ok(storage);
+        }
+        let storage = match self.meta(STORAGE_KEY)? {
+            Some(raw) => serde_json::from_str(&raw).map_err(|e| {
+                UmapError::corrupt(format!("unreadable vector storage metadata: {e}"))
+            })?,
+            None => VectorStorage::default(),
+        };
+        *self.storage.borrow_mut() = Some(storage);
+        Ok(storage)
+    }
+
+    /// Record `storage` for a new collection, or fail if existing chunks were
+    /// stored differently.
+    pub fn ensure_vector_storage(&self, storage: &VectorStorage) -> Result<()> {
+        let current = self.vector_storage()?;
+        if current == *storage {
+            return Ok(());
+        }
+        if self.count_chunks()? > 0 {
+            return Err(UmapError::invalid(format!(
+                "collection stores vectors as {} but {} was requested",
+                describe_storage(&current),
+                describe_storage(storage)
+            )));
+        }
+        let raw = serde_json::to_string(storage)
+            .map_err(|e| UmapError::invalid(format!("cannot encode vector storage: {e}")))?;
+        self.set_meta(STORAGE_KEY, &raw)?;
+        *self.storage.borrow_mut() = Some(*storage);
+        Ok(())
+    }
+
+    pub fn meta(&self, key: &str) -> Result<Option<String>> {
+        let value = self
+            .conn
+            .query_row(
+                "SELECT value FROM collection_meta WHERE key = ?1",
+                params![key],
+                |row| row.get(0),
+            )
+            .optional()?;
+        Ok(value)
+    }
+
+    pub fn set_meta(&self, key: &str, value: &str) -> Result<()> {
+        self.conn.execute(
+            "INSERT INTO collection_meta (key, value) VALUES (?1, ?2)
+             ON CONFLICT(key) DO UPDATE SET value = excluded.value",
+            params![key, value],
+        )?;
+        Ok(())
+    }
+
+    /// The embedder this collection was built with, if one has been recorded.
+    pub fn embedder_info(&self) -> Result<Option<EmbedderInfo>> {
+        if let Some(info) = self.embedder.borrow().as_ref() {
+            return Ok(Some(info.clone()));
+        }
+        let Some(raw) = self.meta(EMBEDDER_KEY)? else {
+            return Ok(None);
+        };
+        let info: EmbedderInfo = serde_json::from_str(&raw).map_err(|e| {
+            UmapError::corrupt(format!("unreadable embedder metadata in collection: {e}"))
+        })?;
+        *self.embedder.borrow_mut() = Some(info.clone());
+        Ok(Some(info))
+    }
+
+    /// Fail unless vectors from `info` can be compared with the stored ones.
+    pub fn check_embedder(&self, info: &EmbedderInfo) -> Result<()> {
+        match self.embedder_info()? {
+            Some(stored) if !stored.is_compatible(info) => {
+                if stored.name == info.name && stored.dim != info.dim {
+                    return Err(UmapError::DimensionMismatch {
+                        expected: stored.dim,
+                        actual: info.dim,
+                    });
+                }
+                Err(UmapError::EmbedderMismatch {
+                    stored: stored.describe(),
+                    requested: info.describe(),
+                })
+            }
+            Some(_) => Ok(()),
+            None => {
+                // Collections created before metadata existed: at least check the dim
+                let mut stmt = self.conn.prepare("SELECT DISTINCT dim FROM chunks")?;
+                let dims = stmt
+                    .query_map([], |row| row.get::<_, i64>(0))?
+                    .collect::<std::result::Result<Vec<_>, _>>()?;
+                match dims.iter().find(|d| **d as usize != info.dim) {
+                    Some(d) => Err(UmapError::DimensionMismatch {
+                        expected: *d as usize,
+                        actual: info.dim,
+                    }),
+                    None => Ok(()),
+                }
+            }
+        }
+    }
+
+    /// Validate `info` against the collection and record it if none is stored yet.
+    pub fn ensure_embedder(&self, info: &EmbedderInfo) -> Result<()> {
+        self.check_embedder(info)?;
+        if self.embedder_info()?.is_none() {
+            let raw = serde_json::to_string(info)
+                .map_err(|e| UmapError::invalid(format!("cannot encode embedder info: {e}")))?;
+            self.set_meta(EMBEDDER_KEY, &raw)?;
+            *self.embedder.borrow_mut() = Some(info.clone());
+        }
+        Ok(())
+    }
+
+    /// Opaque fitted state an embedder stored under `key` (e.g. a projection matrix).
+    pub fn state_blob(&self, key: &str) -> Result<Option<Vec<u8>>> {
+        let value = self
+            .conn
+            .query_row(
+                "SELECT value FROM embedder_state WHERE key = ?1",
+                params![key],
+                |row| row.get(0),
+            )
+            .optional()?;
+        Ok(value)
+    }
+
+    pub fn set_state_blob(&self, key: &str, value: &[u8]) -> Result<()> {
+        self.conn.execute(
+            "INSERT INTO embedder_state (key, value) VALUES (?1, ?2)
+             ON CONFLICT(key) DO UPDATE SET value = excluded.value",
+            params![key, value],
+        )?;
+        Ok(())
+    }
+
+    pub fn term_stats(&self) -> Result<Vec<TermStat>> {
+        let mut stmt = self
+            .conn
+            .prepare("SELECT term, df, idx FROM tfidf_terms ORDER BY term")?;
+        let rows = stmt.query_map([], |row| {
+            Ok(TermStat {
+                term: row.get(0)?,
+                df: row.get::<_, i64>(1)? as u64,
+                index: row.get::<_, Option<i64>>(2)?.map(|i| i as usize),
             })
         })?;
         let mut out = Vec::new();
@@ -106,6 +435,99 @@ impl Db {
         Ok(out)
     }
 
+    /// Run `f` in one write transaction, committed if it succeeds and rolled
+    /// back otherwise. The write lock is taken up front (`BEGIN IMMEDIATE`),
+    /// so a read-modify-write of embedder state cannot interleave with
+    /// another connection's; other writers wait for it to finish.
+    pub fn write_transaction<T>(&self, f: impl FnOnce() -> Result<T>) -> Result<T> {
+        self.conn.execute_batch("BEGIN IMMEDIATE")?;
+        match f() {
+            Ok(value) => {
+                self.conn.execute_batch("COMMIT")?;
+                Ok(value)
+            }
+            Err(e) => {
+                let _ = self.conn.execute_batch("ROLLBACK");
+                // Records cached during the transaction may have been undone
+                *self.embedder.borrow_mut() = None;
+                *self.storage.borrow_mut() = None;
+                Err(e)
+            }
+        }
+    }
+
+    /// Replace the stored term statistics atomically. A savepoint, so it can
+    /// run inside [`Db::write_transaction`].
+    pub fn save_term_stats(&self, stats: &[TermStat]) -> Result<()> {
+        self.conn.execute_batch("SAVEPOINT term_stats")?;
+        let saved = (|| -> Result<()> {
+            self.conn.execute("DELETE FROM tfidf_terms", [])?;
+            let mut stmt = self
+                .conn
+                .prepare("INSERT INTO tfidf_terms (term, df, idx) VALUES (?1, ?2, ?3)")?;
+            for st in stats {
+                stmt.execute(params![st.term, st.df as i64, st.index.map(|i| i as i64)])?;
+            }
+            Ok(())
+        })();
+        if saved.is_err() {
+            let _ = self.conn.execute_batch("ROLLBACK TO term_stats");
+        }
+        self.conn.execute_batch("RELEASE term_stats")?;
+        saved
+    }
+
+    /// Record how `source` was chunked, replacing any earlier record.
+    pub fn record_source(
+        &self,
+        source: &str,
+        chunker: &str,
+        params: &serde_json::Value,
+        chunks: i64,
+    ) -> Result<()> {
+        let ingested_at = OffsetDateTime::now_utc()
+            .format(&time::format_description::well_known::Rfc3339)
+            .unwrap();
+        self.conn.execute(
+            "INSERT OR REPLACE INTO sources (source, chunker, params, chunks, ingested_at)
+             VALUES (?1, ?2, ?3, ?4, ?5)",
+            params![source, chunker, params.to_string(), chunks, ingested_at],
+        )?;
+        Ok(())
+    }
+
+    /// Every recorded source, oldest first. Sources ingested before chunkers
+    /// were recorded are missing.
+    pub fn sources(&self) -> Result<Vec<SourceRecord>> {
+        let mut stmt = self.conn.prepare(
+            "SELECT source, chunker, params, chunks, ingested_at FROM sources ORDER BY ingested_at, source",
+        )?;
+        let rows = stmt.query_map([], |row| {
+            Ok((
+                row.get::<_, String>(0)?,
+                row.get::<_, String>(1)?,
+                row.get::<_, String>(2)?,
+                row.get::<_, i64>(3)?,
+                row.get::<_, String>(4)?,
+            ))
+        })?;
+        let mut out = Vec::new();
+        for r in rows {
+            let (source, chunker, params, chunks, ingested_at) = r?;
+            let params = serde_json::from_str(&params).map_err(|e| {
+                UmapError::corrupt(format!("unreadable chunker params for {source}: {e}"))
+            })?;
+            out.push(SourceRecord {
+                source,
+                chunker,
+                params,
+                chunks,
+                ingested_at,
+            });
+        }
+        Ok(out)
+    }
+
     pub fn count_chunks(&self) -> Result<i64> {
         let mut stmt = self.conn.prepare("SELECT COUNT(*) FROM chunks")?;
         let cnt: i64 = stmt.query_row([], |row| row.get(0))?;
@@ -113,4 +535,98 @@ impl Db {
     }
 }
 
-// helper removed; inline conversion in query_map
+fn describe_storage(storage: &VectorStorage) -> String {
+    let name = match storage.quantization {
+        Quantization::None => "f32",
+        Quantization::Int8 => "int8",
+        Quantization::Binary => "binary",
+    };
+    if storage.keep_full_precision && storage.quantization != Quantization::None {
+        format!("{name} (with full precision)")
+    } else {
+        name.to_string()
+    }
+}
+
+fn check_vector_len(quantization: Quantization, id: i64, dim: i64, blob: &[u8]) -> Result<()> {
+    let expected = quantization.encoded_len(dim.max(0) as usize);
+    if dim < 0 || blob.len() != expected {
+        return Err(UmapError::CorruptVector {
+            id,
+            reason: format!(
+                "expected {} bytes for dim {}, got {}",
+                expected,
+                dim,
+                blob.len()
+            ),
+        });
+    }
+    Ok(())
+}
+
+fn decode_vector(quantization: Quantization, id: i64, dim: i64, blob: &[u8]) -> Result<Vec<f32>> {
+    check_vector_len(quantization, id, dim, blob)?;
+    Ok(quantization.decode(dim as usize, blob))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::embedding::{Embedder, Vocabulary};
+    use crate::tfidf::TfidfEmbedder;
+
+    #[test]
+    fn unreadable_metadata_is_a_corrupt_collection() {
+        let db = Db::open(":memory:").unwrap();
+        db.set_meta(EMBEDDER_KEY, "{not json").unwrap();
+        db.set_meta(STORAGE_KEY, "[]").unwrap();
+        assert!(matches!(
+            db.embedder_info(),
+            Err(UmapError::CorruptCollection(_))
+        ));
+        assert!(matches!(
+            db.vector_storage(),
+            Err(UmapError::CorruptCollection(_))
+        ));
+    }
+
+    #[test]
+    fn concurrent_fits_keep_every_update() {
+        let path = std::env::temp_dir().join(format!("umap-fits-{}.db", std::process::id()));
+        let path = path.to_str().unwrap();
+        Db::open(path).unwrap();
+        std::thread::scope(|scope| {
+            for t in 0..4 {
+                scope.spawn(move || {
+                    let db = Db::open(path).unwrap();
+                    let mut tfidf = TfidfEmbedder::new(64, false, Vocabulary::Learned);
+                    for i in 0..10 {
+                        db.write_transaction(|| {
+                            tfidf.load_state(&db)?;
+                            tfidf.fit(&[&format!("writer {t} document {i}"), "shared words"])?;
+                            tfidf.save_state(&db)
+                        })
+                        .unwrap();
+                    }
+                });
+            }
+        });
+        let mut tfidf = TfidfEmbedder::new(64, false, Vocabulary::Learned);
+        tfidf.load_state(&Db::open(path).unwrap()).unwrap();
+        assert_eq!(tfidf.doc_count(), 80);
+        for suffix in ["", "-wal", "-shm"] {
+            let _ = std::fs::remove_file(format!("{path}{suffix}"));
+        }
+    }
+
+    #[test]
+    fn failed_transactions_leave_nothing() {
+        let db = Db::open(":memory:").unwrap();
+        let failed: Result<()> = db.write_transaction(|| {
+            db.set_meta("tfidf_doc_count", "3")?;
+            Err(UmapError::invalid("embedding failed"))
+        });
+        assert!(failed.is_err());
+        assert_eq!(db.meta("tfidf_doc_count").unwrap(), None);
+    }
+}
@@ -1,42 +1,398 @@
+use std::fs::File;
+use std::io::{Read, Seek, SeekFrom};
+use std::path::Path;
+use std::str::FromStr;
+
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+
+use crate::charngram::CharNgramEmbedder;
+use crate::db::Db;
+use crate::error::{Result, UmapError};
+use crate::lsa::LsaEmbedder;
+use crate::remote::{RemoteApi, RemoteEmbedder};
+use crate::scoring;
+use crate::tfidf::TfidfEmbedder;
+use crate::tokenizer::{Tokenizer, TokenizerConfig};
+use crate::wordvec::{Pooling, WordVectorEmbedder};
+
+/// Turns text into fixed-size vectors. Implementations are selected by name
+/// through [`EmbedderConfig`] so call sites never name a concrete type.
+pub trait Embedder: Send + Sync {
+    /// Registry name, e.g. `"hasher"`.
+    fn name(&self) -> &str;
+    fn dim(&self) -> usize;
+    /// Bumped whenever the same input would produce a different vector.
+    fn version(&self) -> u32 {
+        1
+    }
+    /// Parameters that affect the output beyond name and dim (hashing scheme, etc.).
+    fn params(&self) -> serde_json::Value {
+        serde_json::Value::Null
+    }
+    /// Configuration that rebuilds this embedder through [`build_embedder`].
+    fn config(&self) -> EmbedderConfig;
+    /// Whether a text always maps to the same vector for a given [`EmbedderInfo`],
+    /// so vectors can be cached by content. False when fitted corpus state matters.
+    fn cacheable(&self) -> bool {
+        true
+    }
+
+    /// Whether the embedder can embed yet. False for embedders that need
+    /// corpus state (see [`Embedder::fit`]) before their first ingest.
+    fn fitted(&self) -> bool {
+        true
+    }
+
+    /// Update corpus statistics from a batch of documents about to be ingested.
+    /// Stateless embedders ignore this.
+    fn fit(&mut self, _texts: &[&str]) -> Result<()> {
+        Ok(())
+    }
+    /// Restore fitted state from the collection, if any.
+    fn load_state(&mut self, _db: &Db) -> Result<()> {
+        Ok(())
+    }
+    /// Persist fitted state so queries are embedded consistently with the corpus.
+    fn save_state(&self, _db: &Db) -> Result<()> {
+        Ok(())
+    }
+
+    fn embed_batch(&self, texts: &[&str]) -> Result<Vec<Vec<f32>>>;
+
+    fn embed(&self, text: &str) -> Result<Vec<f32>> {
+        let mut out = self.embed_batch(&[text])?;
+        out.pop()
+            .ok_or_else(|| UmapError::Numerical(format!("{} returned no vector", self.name())))
+    }
+
+    fn info(&self) -> EmbedderInfo {
+        EmbedderInfo {
+            name: self.name().to_string(),
+            version: self.version(),
+            dim: self.dim(),
+            params: self.params(),
+            config: self.config(),
+        }
+    }
+}
+
+/// Identity of the embedder a collection was built with, persisted by [`crate::Db`].
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct EmbedderInfo {
+    pub name: String,
+    pub version: u32,
+    pub dim: usize,
+    pub params: serde_json::Value,
+    pub config: EmbedderConfig,
+}
+
+impl EmbedderInfo {
+    /// Two embedders are compatible when they would produce the same vectors;
+    /// `config` is not compared since it may carry incidental settings.
+    pub fn is_compatible(&self, other: &EmbedderInfo) -> bool {
+        self.name == other.name
+            && self.version == other.version
+            && self.dim == other.dim
+            && self.params == other.params
+    }
+
+    pub fn describe(&self) -> String {
+        format!("{} v{} (dim {})", self.name, self.version, self.dim)
+    }
+}
+
+/// Everything needed to construct an embedder by name. Fields that only some
+/// embedders use default so older collection records still deserialize.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct EmbedderConfig {
+    pub name: String,
+    pub dim: usize,
+    /// TF-IDF: use `1 + ln(tf)` instead of raw term counts
+    #[serde(default)]
+    pub sublinear_tf: bool,
+    /// TF-IDF: how terms map to vector components
+    #[serde(default)]
+    pub vocabulary: Vocabulary,
+    /// LSA: size of the TF-IDF space the SVD is fitted in (`dim` is the output)
+    #[serde(default = "default_vocab_size")]
+    pub vocab_size: usize,
+    /// Word vectors / transformer: vector file or model directory; `dim` is
+    /// taken from it
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub path: Option<String>,
+    /// Word vectors: how token vectors are combined
+    #[serde(default)]
+    pub pooling: Pooling,
+    /// Transformer: L2-normalize the pooled output
+    #[serde(default = "default_normalize")]
+    pub normalize: bool,
+    /// Transformer / remote: texts per forward pass or request
+    #[serde(default = "default_batch_size")]
+    pub batch_size: usize,
+    /// Remote: wire protocol of the embedding server
+    #[serde(default)]
+    pub api: RemoteApi,
+    /// Remote: server base URL
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub endpoint: Option<String>,
+    /// Remote: model name sent with each request
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub model: Option<String>,
+    /// Remote: per-request timeout
+    #[serde(default = "default_timeout_secs")]
+    pub timeout_secs: u64,
+    /// Remote: retries on connection errors, 429 and 5xx responses
+    #[serde(default = "default_max_retries")]
+    pub max_retries: u32,
+    /// Char n-grams: n-gram lengths, inclusive
+    #[serde(default = "default_ngrams")]
+    pub ngrams: (usize, usize),
+    /// Char n-grams: share of similarity given to whole-word hashing (0 = none)
+    #[serde(default)]
+    pub word_weight: f32,
+    /// Hasher, char n-grams, TF-IDF, LSA, word vectors: how text is split into tokens
+    #[serde(default)]
+    pub tokenizer: TokenizerConfig,
+}
+
+fn default_vocab_size() -> usize {
+    4096
+}
+
+fn default_ngrams() -> (usize, usize) {
+    (3, 5)
+}
+
+fn default_normalize() -> bool {
+    true
+}
+
+fn default_batch_size() -> usize {
+    32
+}
+
+fn default_timeout_secs() -> u64 {
+    30
+}
+
+fn default_max_retries() -> u32 {
+    3
+}
+
+impl Default for EmbedderConfig {
+    fn default() -> Self {
+        Self {
+            name: "hasher".to_string(),
+            dim: 512,
+            sublinear_tf: false,
+            vocabulary: Vocabulary::default(),
+            vocab_size: default_vocab_size(),
+            path: None,
+            pooling: Pooling::default(),
+            normalize: default_normalize(),
+            batch_size: default_batch_size(),
+            api: RemoteApi::default(),
+            endpoint: None,
+            model: None,
+            timeout_secs: default_timeout_secs(),
+            max_retries: default_max_retries(),
+            ngrams: default_ngrams(),
+            word_weight: 0.0,
+            tokenizer: TokenizerConfig::default(),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Vocabulary {
+    /// Terms are hashed into `dim` buckets; no vocabulary to store
+    #[default]
+    Hashing,
+    /// The `dim` most document-frequent terms seen at ingest each get a component
+    Learned,
+}
+
+impl FromStr for Vocabulary {
+    type Err = UmapError;
+
+    fn from_str(s: &str) -> Result<Self> {
+        match s.to_lowercase().as_str() {
+            "hashing" | "hashed" => Ok(Vocabulary::Hashing),
+            "learned" => Ok(Vocabulary::Learned),
+            other => Err(UmapError::invalid(format!(
+                "unknown vocabulary mode: {other} (expected hashing or learned)"
+            ))),
+        }
+    }
+}
+
+/// Names accepted by [`build_embedder`].
+pub const EMBEDDER_NAMES: &[&str] = &[
+    "hasher",
+    "charngram",
+    "tfidf",
+    "lsa",
+    "wordvec",
+    "transformer",
+    "remote",
+];
+
+pub fn build_embedder(config: &EmbedderConfig) -> Result<Box<dyn Embedder>> {
+    // A remote model's dim of 0 means it is probed from the server
+    if config.dim == 0 && !config.name.eq_ignore_ascii_case("remote") {
+        return Err(UmapError::invalid("embedding dim must be > 0"));
+    }
+    match config.name.to_lowercase().as_str() {
+        "hasher" => Ok(Box::new(
+            HasherEmbedder::new(config.dim).with_tokenizer(config.tokenizer.clone()),
+        )),
+        "charngram" => Ok(Box::new(
+            CharNgramEmbedder::new(config.dim, config.ngrams, config.word_weight)?
+                .with_tokenizer(config.tokenizer.clone()),
+        )),
+        "tfidf" => Ok(Box::new(
+            TfidfEmbedder::new(config.dim, config.sublinear_tf, config.vocabulary)
+                .with_tokenizer(config.tokenizer.clone()),
+        )),
+        "lsa" => {
+            if config.vocab_size < config.dim {
+                return Err(UmapError::invalid(
+                    "lsa vocab_size must be at least the output dim",
+                ));
+            }
+            Ok(Box::new(
+                LsaEmbedder::new(
+                    config.dim,
+                    config.vocab_size,
+                    config.sublinear_tf,
+                    config.vocabulary,
+                )
+                .with_tokenizer(config.tokenizer.clone()),
+            ))
+        }
+        "wordvec" => {
+            let path = config
+                .path
+                .as_deref()
+                .ok_or_else(|| UmapError::invalid("wordvec embedder needs a vector file path"))?;
+            Ok(Box::new(
+                WordVectorEmbedder::open(path, config.pooling)?
+                    .with_tokenizer(config.tokenizer.clone()),
+            ))
+        }
+        #[cfg(feature = "transformers")]
+        "transformer" => {
+            let path = config.path.as_deref().ok_or_else(|| {
+                UmapError::invalid("transformer embedder needs a model directory")
+            })?;
+            Ok(Box::new(crate::transformer::TransformerEmbedder::open(
+                path,
+                config.normalize,
+                config.batch_size,
+            )?))
+        }
+        #[cfg(not(feature = "transformers"))]
+        "transformer" => Err(UmapError::invalid(
+            "transformer embedder requires building with the `transformers` feature",
+        )),
+        "remote" => {
+            let (Some(endpoint), Some(model)) = (&config.endpoint, &config.model) else {
+                return Err(UmapError::invalid(
+                    "remote embedder needs an endpoint and a model name",
+                ));
+            };
+            Ok(Box::new(RemoteEmbedder::open(
+                config.api,
+                endpoint,
+                model,
+                config.dim,
+                config.batch_size,
+                config.timeout_secs,
+                config.max_retries,
+            )?))
+        }
+        other => Err(UmapError::invalid(format!(
+            "unknown embedder: {other} (expected one of {})",
+            EMBEDDER_NAMES.join(", ")
+        ))),
+    }
+}
+
 pub struct HasherEmbedder {
     dim: usize,
+    tokenizer: Tokenizer,
     // Optional seeded projections for signed hashing; here we do simple hashing trick
 }
 
 impl HasherEmbedder {
     pub fn new(dim: usize) -> Self {
-        Self { dim }
+        Self {
+            dim,
+            tokenizer: Tokenizer::default(),
+        }
     }
 
-    pub fn dim(&self) -> usize {
-        self.dim
+    pub fn with_tokenizer(mut self, config: TokenizerConfig) -> Self {
+        self.tokenizer = Tokenizer::new(config);
+        self
     }
 
-    pub fn embed(&self, text: &str) -> Vec<f32> {
-        let tokens = tokenize(text);
+    fn embed_one(&self, text: &str) -> Vec<f32> {
+        let tokens = self.tokenizer.tokenize(text);
         let mut vec = vec![0f32; self.dim];
         for t in tokens {
-            let h = fxhash(&t);
-            let idx = (h % (self.dim as u64)) as usize;
-            // signed hashing using lowest bit
-            let sign = if (h & 1) == 0 { 1.0 } else { -1.0 };
+            let (idx, sign) = signed_hash(fxhash(&t), self.dim);
             vec[idx] += sign;
         }
         l2_normalize(vec)
     }
 }
 
+impl Embedder for HasherEmbedder {
+    fn name(&self) -> &str {
+        "hasher"
+    }
+
+    fn dim(&self) -> usize {
+        self.dim
+    }
+
+    // 2: hash signs come from the top bit rather than the bucket's low bit
+    fn version(&self) -> u32 {
+        2
+    }
+
+    fn params(&self) -> serde_json::Value {
+        serde_json::json!({
+            "hash": "fnv1a-64",
+            "signed": true,
+            "tokenizer": self.tokenizer.config().describe(),
+        })
+    }
+
+    fn config(&self) -> EmbedderConfig {
+        EmbedderConfig {
+            name: "hasher".to_string(),
+            dim: self.dim,
+            tokenizer: self.tokenizer.config().clone(),
+            ..EmbedderConfig::default()
+        }
+    }
+
+    fn embed_batch(&self, texts: &[&str]) -> Result<Vec<Vec<f32>>> {
+        Ok(texts.iter().map(|t| self.embed_one(t)).collect())
+    }
+}
+
+/// Lowercase alphanumeric words of two or more bytes; see [`Tokenizer`] for
+/// the configurable version.
 pub fn tokenize(text: &str) -> Vec<String> {
-    text.to_lowercase()
-        .split(|c: char| !c.is_alphanumeric() && c != '\'')
-        .map(|s| s.trim_matches('\''))
-        .filter(|s| !s.is_empty())
-        .filter(|s| s.len() > 1)
-        .map(|s| s.to_string())
-        .collect()
+    Tokenizer::default().words(text)
 }
 
-fn l2_normalize(mut v: Vec<f32>) -> Vec<f32> {
+pub(crate) fn l2_normalize(mut v: Vec<f32>) -> Vec<f32> {
     let norm = (v.iter().map(|x| (*x as f64) * (*x as f64)).sum::<f64>()).sqrt() as f32;
     if norm > 0.0 {
         for x in &mut v {
@@ -46,7 +402,32 @@ fn l2_normalize(mut v: Vec<f32>) -> Vec<f32> {
     v
 }
 
-fn fxhash(s: &str) -> u64 {
+/// Short content hash of model files: their sizes and first and last 64 KiB.
+/// Cheap even for large weights, yet changes when a file is replaced, so
+/// path-based embedders put it in their params.
+pub(crate) fn file_fingerprint(paths: &[&Path]) -> Result<String> {
+    const EDGE: u64 = 64 * 1024;
+    let mut h = Sha256::new();
+    let mut buf = Vec::new();
+    for path in paths {
+        let mut file = File::open(path)?;
+        let len = file.metadata()?.len();
+        h.update(len.to_le_bytes());
+        buf.clear();
+        (&mut file).take(EDGE).read_to_end(&mut buf)?;
+        if len > EDGE {
+            file.seek(SeekFrom::Start(len.saturating_sub(EDGE).max(EDGE)))?;
+            file.take(EDGE).read_to_end(&mut buf)?;
+        }
+        h.update(&buf);
+    }
+    Ok(h.finalize()[..8]
+        .iter()
+        .map(|b| format!("{b:02x}"))
+        .collect())
+}
+
+pub(crate) fn fxhash(s: &str) -> u64 {
     // A simple 64-bit hash; not cryptographic. Fowler–Noll–Vo (FNV-1a)
     let mut hash: u64 = 0xcbf29ce484222325;
     for b in s.as_bytes() {
@@ -56,15 +437,37 @@ fn fxhash(s: &str) -> u64 {
     hash
 }
 
+/// Bucket and sign for feature hashing of `h` into `dim` components. The sign
+/// comes from the top bit, which the bucket (`h % dim`) does not determine;
+/// taking it from the low bit would give every bucket a fixed sign when `dim`
+/// is even.
+pub(crate) fn signed_hash(h: u64, dim: usize) -> (usize, f32) {
+    let sign = if h >> 63 == 0 { 1.0 } else { -1.0 };
+    ((h % dim as u64) as usize, sign)
+}
+
 pub fn cosine_similarity(a: &[f32], b: &[f32]) -> f32 {
-    let mut dot = 0.0f32;
-    let mut na = 0.0f32;
-    let mut nb = 0.0f32;
-    for i in 0..a.len() {
-        dot += a[i] * b[i];
-        na += a[i] * a[i];
-        nb += b[i] * b[i];
-    }
-    let denom = (na.sqrt() * nb.sqrt()).max(1e-8);
-    dot / denom
+    debug_assert_eq!(a.len(), b.len(), "cosine_similarity on unequal lengths");
+    let denom = (scoring::norm(a) * scoring::norm(b)).max(1e-8);
+    scoring::dot(a, b) / denom
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn signs_are_independent_of_the_bucket() {
+        let dim = 64;
+        let mut seen = vec![(false, false); dim];
+        for i in 0..10_000 {
+            let (idx, sign) = signed_hash(fxhash(&format!("term{i}")), dim);
+            if sign > 0.0 {
+                seen[idx].0 = true;
+            } else {
+                seen[idx].1 = true;
+            }
+        }
+        assert!(seen.iter().all(|&(pos, neg)| pos && neg));
+    }
 }
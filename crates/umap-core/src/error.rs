@@ -0,0 +1,66 @@
+use thiserror::Error;
+
+#[derive(Debug, Error)]
+pub enum UmapError {
+    /// A caller-supplied value is out of range or malformed
+    #[error("invalid parameter: {0}")]
+    InvalidParameter(String),
+
+    /// Two vectors or matrices that must agree in size do not
+    #[error("dimension mismatch: expected {expected}, got {actual}")]
+    DimensionMismatch { expected: usize, actual: usize },
+
+    /// The requested embedder differs from the one the collection was built with
+    #[error("embedder mismatch: collection uses {stored}, requested {requested}")]
+    EmbedderMismatch { stored: String, requested: String },
+
+    /// A stored vector blob could not be decoded
+    #[error("corrupt vector for chunk {id}: {reason}")]
+    CorruptVector { id: i64, reason: String },
+
+    /// Collection metadata or embedder state in the database is unreadable
+    #[error("corrupt collection: {0}")]
+    CorruptCollection(String),
+
+    #[error("storage error: {0}")]
+    Storage(#[from] rusqlite::Error),
+
+    /// A model or data file could not be read
+    #[error("io error: {0}")]
+    Io(#[from] std::io::Error),
+
+    /// An embedding service failed or returned something unusable
+    #[error("embedding service error: {0}")]
+    Remote(String),
+
+    /// A decomposition or fit did not produce a usable result
+    #[error("numerical failure: {0}")]
+    Numerical(String),
+}
+
+impl UmapError {
+    /// Stable, machine-readable name of the variant for API responses.
+    pub fn kind(&self) -> &'static str {
+        match self {
+            UmapError::InvalidParameter(_) => "invalid_parameter",
+            UmapError::DimensionMismatch { .. } => "dimension_mismatch",
+            UmapError::EmbedderMismatch { .. } => "embedder_mismatch",
+            UmapError::CorruptVector { .. } => "corrupt_vector",
+            UmapError::CorruptCollection(_) => "corrupt_collection",
+            UmapError::Storage(_) => "storage",
+            UmapError::Io(_) => "io",
+            UmapError::Remote(_) => "remote",
+            UmapError::Numerical(_) => "numerical",
+        }
+    }
+
+    pub(crate) fn invalid(msg: impl Into<String>) -> Self {
+        UmapError::InvalidParameter(msg.into())
+    }
+
+    pub(crate) fn corrupt(msg: impl Into<String>) -> Self {
+        UmapError::CorruptCollection(msg.into())
+    }
+}
+
+pub type Result<T> = std::result::Result<T, UmapError>;
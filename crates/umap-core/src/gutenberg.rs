@@ -0,0 +1,190 @@
+use std::ops::Range;
+use std::sync::LazyLock;
+
+use regex::Regex;
+
+use crate::chunk::{Chunk, Chunker, LineIndex, paragraph_spans};
+use crate::error::Result;
+
+/// The line after which the book starts; older releases end their license
+/// header with "*END*THE SMALL PRINT!" instead.
+static START: LazyLock<Regex> = LazyLock::new(|| {
+    Regex::new(
+        r"(?im)^[ \t]*(?:\*{3}[ \t]*START OF (?:THE|THIS) PROJECT GUTENBERG E-?BOOK|\*END\*THE SMALL PRINT).*$",
+    )
+    .unwrap()
+});
+
+/// The line at which the license footer starts.
+static END: LazyLock<Regex> = LazyLock::new(|| {
+    Regex::new(
+        r"(?im)^[ \t]*(?:\*{3}[ \t]*END OF (?:THE|THIS) PROJECT GUTENBERG E-?BOOK|End of (?:the )?Project Gutenberg)",
+    )
+    .unwrap()
+});
+
+/// First line of a heading: "CHAPTER IV.", "Book Two: The Return", "Chapter
+/// the First", "STAVE 1" or a bare "XII.". The phrase fills the line, or is
+/// followed by `.`, `:` or a dash and a title that does not start lowercase.
+static HEADING: LazyLock<Regex> = LazyLock::new(|| {
+    const ORDINAL: &str = "first|second|third|fourth|fifth|sixth|seventh|eighth|ninth|tenth|last";
+    const NUMBER: &str = "one|two|three|four|five|six|seven|eight|nine|ten|eleven|twelve|thirteen|fourteen|fifteen|sixteen|seventeen|eighteen|nineteen|twenty|thirty|forty|fifty";
+    // A title must not start lowercase, so "I. e. nothing" is not "I."
+    const TITLE: &str = r"(?:\s*[^\p{Ll}\s].*)?";
+    Regex::new(&format!(
+        r"^(?:(?i:(book|part|volume|chapter|canto|stave|letter)\s+(?:\d+|[ivxlcdm]+|the\s+(?:{ORDINAL})|(?:{NUMBER}|{ORDINAL})(?:-\w+)?))(?:\s*(?:[.:—–]|--){TITLE})?|[IVXLC]+\.{TITLE})$"
+    ))
+    .unwrap()
+});
+
+/// A Project Gutenberg text reduced to the book itself.
+pub struct GutenbergText {
+    /// Byte range of the original kept, between the license header and footer
+    pub body: Range<usize>,
+    /// `original[body]` with hard-wrapped lines joined into one line per
+    /// paragraph. Newlines become spaces, so offsets are unchanged.
+    pub text: String,
+    /// Chapter headings: byte offset in the original and section path, e.g.
+    /// "BOOK II > CHAPTER III. The Storm"
+    pub chapters: Vec<(usize, String)>,
+}
+
+/// Strip the Gutenberg start/end boilerplate from `input`, unwrap its
+/// paragraphs and find its chapter headings. Text without the markers is
+/// kept whole.
+pub fn clean_gutenberg(input: &str) -> GutenbergText {
+    let start = START.find(input).map_or(0, |m| m.end());
+    let end = END.find_at(input, start).map_or(input.len(), |m| m.start());
+    let body = start..end.max(start);
+    let original = &input[body.clone()];
+
+    let mut bytes = original.as_bytes().to_vec();
+    let mut chapters = Vec::new();
+    let mut path: Vec<(usize, String)> = Vec::new();
+    for para in paragraph_spans(original) {
+        if let Some((level, title)) = heading(&original[para.clone()]) {
+            while path.last().is_some_and(|(l, _)| *l >= level) {
+                path.pop();
+            }
+            path.push((level, title));
+            let section = path
+                .iter()
+                .map(|(_, t)| t.as_str())
+                .collect::<Vec<_>>()
+                .join(" > ");
+            chapters.push((body.start + para.start, section));
+        }
+        for b in &mut bytes[para] {
+            if matches!(*b, b'\n' | b'\r') {
+                *b = b' ';
+            }
+        }
+    }
+    // Only ASCII bytes were replaced, with ASCII
+    let text = String::from_utf8(bytes).expect("unwrapping keeps UTF-8");
+    GutenbergText {
+        body,
+        text,
+        chapters,
+    }
+}
+
+/// Level and title of a paragraph that is a chapter heading: a few short
+/// lines, the first a heading line (see [`HEADING`]) and the rest its title. Books, parts and volumes contain chapters.
+fn heading(para: &str) -> Option<(usize, String)> {
+    if para.len() > 150 || para.lines().count() > 3 {
+        return None;
+    }
+    let caps = HEADING.captures(para.lines().next()?.trim())?;
+    let level = match caps.get(1).map(|m| m.as_str().to_lowercase()) {
+        Some(kind) if matches!(kind.as_str(), "book" | "part" | "volume") => 1,
+        _ => 2,
+    };
+    Some((level, para.split_whitespace().collect::<Vec<_>>().join(" ")))
+}
+
+/// Chunks the book inside a Project Gutenberg text with another chunker,
+/// leaving out the license header and footer. Chunk texts are unwrapped (see
+/// [`GutenbergText::text`]) while spans still point into the original file,
+/// and each chunk records the chapter its middle falls in as its section.
+pub struct GutenbergChunker<'a> {
+    inner: Box<dyn Chunker + 'a>,
+}
+
+impl<'a> GutenbergChunker<'a> {
+    pub fn new(inner: Box<dyn Chunker + 'a>) -> Self {
+        Self { inner }
+    }
+}
+
+impl Chunker for GutenbergChunker<'_> {
+    fn name(&self) -> &str {
+        self.inner.name()
+    }
+
+    fn params(&self) -> serde_json::Value {
+        let mut params = self.inner.params();
+        if let Some(map) = params.as_object_mut() {
+            map.insert("gutenberg".into(), true.into());
+        }
+        params
+    }
+
+    fn chunk(&self, input: &str) -> Result<Vec<Chunk>> {
+        let book = clean_gutenberg(input);
+        let lines = LineIndex::new(input);
+        let mut chunks = self.inner.chunk(&book.text)?;
+        for chunk in &mut chunks {
+            let (start, end) = (
+                book.body.start + chunk.span.start,
+                book.body.start + chunk.span.end,
+            );
+            chunk.span = lines.span(start, end);
+            if chunk.section.is_none() {
+                let middle = start + (end - start) / 2;
+                let i = book.chapters.partition_point(|(at, _)| *at <= middle);
+                chunk.section = i.checked_sub(1).map(|i| book.chapters[i].1.clone());
+            }
+        }
+        Ok(chunks)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn recognizes_headings_not_sentences() {
+        let cases: &[(&str, Option<(usize, &str)>)] = &[
+            ("CHAPTER IV.", Some((2, "CHAPTER IV."))),
+            ("CHAPTER IV. The Storm", Some((2, "CHAPTER IV. The Storm"))),
+            ("Book Two: The Return", Some((1, "Book Two: The Return"))),
+            ("Chapter the First", Some((2, "Chapter the First"))),
+            ("Chapter Twenty-One", Some((2, "Chapter Twenty-One"))),
+            ("STAVE 1", Some((2, "STAVE 1"))),
+            ("PART III — The Sea", Some((1, "PART III — The Sea"))),
+            ("XII.", Some((2, "XII."))),
+            ("IV. The Duel", Some((2, "IV. The Duel"))),
+            (
+                "CHAPTER I\nThe Beginning",
+                Some((2, "CHAPTER I The Beginning")),
+            ),
+            ("Part the curtains, she said.", None),
+            ("Book the first coach.", None),
+            ("Part two of the plan failed.", None),
+            ("I. e. nothing", None),
+            ("Chapter and verse.", None),
+            ("CHAPTER IV. the storm", None),
+            ("XII", None),
+        ];
+        for (para, expected) in cases {
+            let got = heading(para);
+            assert_eq!(
+                got.as_ref().map(|(l, t)| (*l, t.as_str())),
+                *expected,
+                "paragraph: {para:?}"
+            );
+        }
+    }
+}
@@ -1,13 +1,53 @@
+pub mod align;
+pub mod cache;
+pub mod charngram;
 pub mod chunk;
+#[cfg(feature = "code")]
+pub mod code;
 pub mod db;
 pub mod embedding;
+pub mod error;
+pub mod gutenberg;
+pub mod lsa;
+pub mod markdown;
+pub mod preprocess;
+pub mod quantize;
 pub mod reduction;
+pub mod remote;
+pub mod scoring;
 pub mod search;
+pub mod semantic;
+pub mod sentence;
+pub mod tfidf;
+pub mod tokenizer;
+#[cfg(feature = "transformers")]
+pub mod transformer;
 pub mod types;
+pub mod wordvec;
 
+pub use align::*;
+pub use cache::*;
+pub use charngram::*;
 pub use chunk::*;
+#[cfg(feature = "code")]
+pub use code::*;
 pub use db::*;
 pub use embedding::*;
+pub use error::*;
+pub use gutenberg::*;
+pub use lsa::*;
+pub use markdown::*;
+pub use preprocess::*;
+pub use quantize::*;
 pub use reduction::*;
+pub use remote::*;
+pub use scoring::*;
 pub use search::*;
+pub use semantic::*;
+pub use sentence::*;
+pub use tfidf::*;
+pub use tokenizer::*;
+#[cfg(feature = "transformers")]
+pub use transformer::*;
 pub use types::*;
+pub use wordvec::*;
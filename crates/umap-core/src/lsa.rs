@@ -0,0 +1,388 @@
+use linfa_linalg::eigh::Eigh;
+use linfa_linalg::qr::QR;
+use ndarray::Array2;
+use rand::prelude::*;
+
+use crate::db::Db;
+use crate::embedding::{Embedder, EmbedderConfig, Vocabulary, l2_normalize};
+use crate::error::{Result, UmapError};
+use crate::quantize::{decode_f32, encode_f32};
+use crate::tfidf::TfidfEmbedder;
+use crate::tokenizer::TokenizerConfig;
+
+const PROJECTION_KEY: &str = "lsa_projection";
+const RANK_KEY: &str = "lsa_rank";
+// Extra random directions and power iterations for the randomized SVD
+const OVERSAMPLE: usize = 10;
+const POWER_ITERS: usize = 2;
+const SVD_SEED: u64 = 42;
+
+/// Latent semantic analysis: TF-IDF vectors projected onto the top singular
+/// directions of the corpus TF-IDF matrix.
+///
+/// The basis is fitted on the first ingest into a collection and then frozen,
+/// together with the TF-IDF statistics it was fitted under, so every stored
+/// vector and every query lives in the same space. Later ingests are projected
+/// onto that basis; start a new collection to refit. Reload the state and
+/// fit inside one [`Db::write_transaction`], so that concurrent first ingests
+/// settle on a single basis rather than each saving their own.
+///
+/// A first ingest with fewer chunks than `dim` cannot span `dim` directions.
+/// The number it does span is stored as the `lsa_rank` meta value, and the
+/// components past it are always zero.
+pub struct LsaEmbedder {
+    dim: usize,
+    tfidf: TfidfEmbedder,
+    // vocab_size x dim, row-major; None until fitted
+    projection: Option<Array2<f32>>,
+    rank: Option<usize>,
+}
+
+impl LsaEmbedder {
+    pub fn new(dim: usize, vocab_size: usize, sublinear_tf: bool, vocabulary: Vocabulary) -> Self {
+        Self {
+            dim,
+            tfidf: TfidfEmbedder::new(vocab_size, sublinear_tf, vocabulary),
+            projection: None,
+            rank: None,
+        }
+    }
+
+    pub fn with_tokenizer(mut self, config: TokenizerConfig) -> Self {
+        self.tfidf = self.tfidf.with_tokenizer(config);
+        self
+    }
+
+    fn vocab_size(&self) -> usize {
+        self.tfidf.dim()
+    }
+
+    /// Number of basis directions the corpus actually spans, once fitted.
+    pub fn rank(&self) -> Option<usize> {
+        self.rank
+    }
+
+    fn tfidf_matrix(&self, texts: &[&str]) -> SparseRows {
+        let rows = texts
+            .iter()
+            .map(|t| {
+                l2_normalize(self.tfidf.weighted(t))
+                    .into_iter()
+                    .enumerate()
+                    .filter(|(_, v)| *v != 0.0)
+                    .map(|(j, v)| (j, v as f64))
+                    .collect()
+            })
+            .collect();
+        SparseRows {
+            cols: self.vocab_size(),
+            rows,
+        }
+    }
+}
+
+/// A chunks x vocabulary matrix holding only each row's nonzero entries;
+/// chunks use a tiny share of the vocabulary.
+struct SparseRows {
+    cols: usize,
+    rows: Vec<Vec<(usize, f64)>>,
+}
+
+impl SparseRows {
+    /// `X M` for a dense `M` with `cols` rows.
+    fn dot(&self, m: &Array2<f64>) -> Array2<f64> {
+        let mut out = Array2::<f64>::zeros((self.rows.len(), m.ncols()));
+        for (i, row) in self.rows.iter().enumerate() {
+            let mut o = out.row_mut(i);
+            for &(j, v) in row {
+                o.scaled_add(v, &m.row(j));
+            }
+        }
+        out
+    }
+
+    /// `X^T M` for a dense `M` with one row per chunk.
+    fn t_dot(&self, m: &Array2<f64>) -> Array2<f64> {
+        let mut out = Array2::<f64>::zeros((self.cols, m.ncols()));
+        for (row, mi) in self.rows.iter().zip(m.rows()) {
+            for &(j, v) in row {
+                out.row_mut(j).scaled_add(v, &mi);
+            }
+        }
+        out
+    }
+}
+
+impl Embedder for LsaEmbedder {
+    fn name(&self) -> &str {
+        "lsa"
+    }
+
+    fn dim(&self) -> usize {
+        self.dim
+    }
+
+    // 2: hash signs come from the top bit rather than the bucket's low bit
+    fn version(&self) -> u32 {
+        2
+    }
+
+    fn params(&self) -> serde_json::Value {
+        serde_json::json!({
+            "vocab_size": self.vocab_size(),
+            "tfidf": self.tfidf.params(),
+            "svd": "randomized",
+        })
+    }
+
+    fn config(&self) -> EmbedderConfig {
+        EmbedderConfig {
+            name: "lsa".to_string(),
+            dim: self.dim,
+            vocab_size: self.vocab_size(),
+            ..self.tfidf.config()
+        }
+    }
+
+    fn cacheable(&self) -> bool {
+        false
+    }
+
+    fn fitted(&self) -> bool {
+        self.projection.is_some()
+    }
+
+    fn fit(&mut self, texts: &[&str]) -> Result<()> {
+        if self.projection.is_some() || texts.is_empty() {
+            return Ok(());
+        }
+        self.tfidf.fit(texts)?;
+        let x = self.tfidf_matrix(texts);
+        let (vk, rank) = truncated_svd_basis(&x, self.dim)?;
+        self.projection = Some(vk.mapv(|v| v as f32));
+        self.rank = Some(rank);
+        Ok(())
+    }
+
+    fn load_state(&mut self, db: &Db) -> Result<()> {
+        self.tfidf.load_state(db)?;
+        self.projection = match db.state_blob(PROJECTION_KEY)? {
+            Some(blob) => {
+                let (rows, cols) = (self.vocab_size(), self.dim);
+                // Callers check the dim against the collection first, so
+                // a blob of another size means the state is damaged
+                if blob.len() != rows * cols * 4 {
+                    return Err(UmapError::corrupt(format!(
+                        "LSA projection is {} bytes, expected {} for {rows} x {cols}",
+                        blob.len(),
+                        rows * cols * 4
+                    )));
+                }
+                Some(
+                    Array2::from_shape_vec((rows, cols), decode_f32(&blob)).map_err(|e| {
+                        UmapError::corrupt(format!("bad LSA projection shape: {e}"))
+                    })?,
+                )
+            }
+            None => None,
+        };
+        self.rank = match db.meta(RANK_KEY)? {
+            Some(v) => Some(
+                v.parse()
+                    .map_err(|_| UmapError::corrupt(format!("bad {RANK_KEY} value: {v}")))?,
+            ),
+            // Collections fitted before the rank was recorded
+            None => self.projection.as_ref().map(|_| self.dim),
+        };
+        Ok(())
+    }
+
+    fn save_state(&self, db: &Db) -> Result<()> {
+        let Some(p) = &self.projection else {
+            return Ok(());
+        };
+        self.tfidf.save_state(db)?;
+        let values: Vec<f32> = p.iter().copied().collect();
+        db.set_state_blob(PROJECTION_KEY, &encode_f32(&values))?;
+        db.set_meta(RANK_KEY, &self.rank.unwrap_or(self.dim).to_string())
+    }
+
+    fn embed_batch(&self, texts: &[&str]) -> Result<Vec<Vec<f32>>> {
+        let Some(p) = &self.projection else {
+            return Err(UmapError::invalid(
+                "LSA embedder has no fitted basis yet; ingest documents first",
+            ));
+        };
+        let mut out = Vec::with_capacity(texts.len());
+        for t in texts {
+            let q = l2_normalize(self.tfidf.weighted(t));
+            let mut v = vec![0f32; self.dim];
+            for (j, qj) in q.iter().enumerate() {
+                if *qj == 0.0 {
+                    continue;
+                }
+                for (c, vc) in v.iter_mut().enumerate() {
+                    *vc += qj * p[(j, c)];
+                }
+            }
+            out.push(l2_normalize(v));
+        }
+        Ok(out)
+    }
+}
+
+/// Top-`k` right singular vectors of `x` (columns x k) via randomized SVD
+/// (Halko et al.): sketch the range of `x`, orthonormalize, and solve the small
+/// eigenproblem. Components beyond the rank of `x` are left as zeros; the
+/// number that are not is returned with the basis.
+fn truncated_svd_basis(x: &SparseRows, k: usize) -> Result<(Array2<f64>, usize)> {
+    let (n, v) = (x.rows.len(), x.cols);
+    let mut basis = Array2::<f64>::zeros((v, k));
+    let l = (k + OVERSAMPLE).min(n).min(v);
+    if l == 0 {
+        return Ok((basis, 0));
+    }
+
+    let mut rng = StdRng::seed_from_u64(SVD_SEED);
+    let omega = Array2::<f64>::from_shape_fn((v, l), |_| rng.r#gen::<f64>() * 2.0 - 1.0);
+    let mut q = orthonormalize(&x.dot(&omega))?;
+    for _ in 0..POWER_ITERS {
+        let z = orthonormalize(&x.t_dot(&q))?;
+        q = orthonormalize(&x.dot(&z))?;
+    }
+
+    // B = Q^T X is small (l x v); its right singular vectors approximate X's
+    let b = x.t_dot(&q).reversed_axes();
+    let (vals, vecs) = b
+        .dot(&b.t())
+        .eigh()
+        .map_err(|e| UmapError::Numerical(format!("LSA eigendecomposition failed: {e}")))?;
+    let mut order: Vec<usize> = (0..vals.len()).collect();
+    order.sort_by(|&a, &c| {
+        vals[c]
+            .partial_cmp(&vals[a])
+            .unwrap_or(std::cmp::Ordering::Equal)
+    });
+    let mut rank = 0;
+    for (c, &idx) in order.iter().take(k).enumerate() {
+        let sigma = vals[idx].max(0.0).sqrt();
+        if sigma < 1e-10 {
+            continue;
+        }
+        // v_c = B^T u_c / sigma_c
+        let u = vecs.column(idx);
+        let col = b.t().dot(&u) / sigma;
+        basis.column_mut(c).assign(&col);
+        rank += 1;
+    }
+    Ok((basis, rank))
+}
+
+fn orthonormalize(y: &Array2<f64>) -> Result<Array2<f64>> {
+    y.qr()
+        .map(|d| d.generate_q())
+        .map_err(|e| UmapError::Numerical(format!("LSA QR failed: {e}")))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn sparse_products_match_dense() {
+        let x = SparseRows {
+            cols: 4,
+            rows: vec![vec![(0, 1.0), (3, 2.0)], vec![], vec![(1, -1.5), (2, 0.5)]],
+        };
+        let mut dense = Array2::<f64>::zeros((3, 4));
+        for (i, row) in x.rows.iter().enumerate() {
+            for &(j, v) in row {
+                dense[(i, j)] = v;
+            }
+        }
+        let m = Array2::from_shape_fn((4, 2), |(i, j)| (i * 2 + j) as f64 - 3.0);
+        assert_eq!(x.dot(&m), dense.dot(&m));
+        let q = Array2::from_shape_fn((3, 2), |(i, j)| (i + j) as f64 * 0.5);
+        assert_eq!(x.t_dot(&q), dense.t().dot(&q));
+    }
+
+    #[test]
+    fn records_rank_of_a_small_first_fit() {
+        let mut lsa = LsaEmbedder::new(8, 64, false, Vocabulary::Learned);
+        let texts = [
+            "the cat sat on the mat",
+            "dogs chase cats around the yard",
+            "stock prices fell sharply today",
+        ];
+        lsa.fit(&texts).unwrap();
+        let rank = lsa.rank().unwrap();
+        assert!((1..=texts.len()).contains(&rank));
+        for v in lsa.embed_batch(&texts).unwrap() {
+            assert_eq!(v.len(), 8);
+            assert!(v[rank..].iter().all(|x| *x == 0.0));
+        }
+    }
+
+    fn temp_db(name: &str) -> String {
+        let path = std::env::temp_dir().join(format!("umap-{name}-{}.db", std::process::id()));
+        path.to_str().unwrap().to_string()
+    }
+
+    fn remove_db(path: &str) {
+        for suffix in ["", "-wal", "-shm"] {
+            let _ = std::fs::remove_file(format!("{path}{suffix}"));
+        }
+    }
+
+    #[test]
+    fn concurrent_first_fits_share_one_basis() {
+        let path = temp_db("lsa-race");
+        Db::open(&path).unwrap();
+        let probe = "rivers and banks";
+        let vectors: Vec<Vec<f32>> = std::thread::scope(|scope| {
+            let handles: Vec<_> = (0..4)
+                .map(|t| {
+                    let path = &path;
+                    scope.spawn(move || {
+                        let db = Db::open(path).unwrap();
+                        let mut lsa = LsaEmbedder::new(2, 64, false, Vocabulary::Learned);
+                        let texts = [
+                            format!("writer {t} writes about rivers"),
+                            format!("writer {t} writes about banks and money"),
+                            "shared text about water".to_string(),
+                        ];
+                        let texts: Vec<&str> = texts.iter().map(String::as_str).collect();
+                        db.write_transaction(|| {
+                            lsa.load_state(&db)?;
+                            lsa.fit(&texts)?;
+                            lsa.save_state(&db)
+                        })
+                        .unwrap();
+                        lsa.embed(probe).unwrap()
+                    })
+                })
+                .collect();
+            handles.into_iter().map(|h| h.join().unwrap()).collect()
+        });
+        let mut stored = LsaEmbedder::new(2, 64, false, Vocabulary::Learned);
+        stored.load_state(&Db::open(&path).unwrap()).unwrap();
+        let expected = stored.embed(probe).unwrap();
+        remove_db(&path);
+        for v in vectors {
+            assert_eq!(v, expected);
+        }
+    }
+
+    #[test]
+    fn damaged_projection_is_a_corrupt_collection() {
+        let db = Db::open(":memory:").unwrap();
+        let mut lsa = LsaEmbedder::new(4, 64, false, Vocabulary::Learned);
+        lsa.fit(&["the cat sat", "stock prices fell"]).unwrap();
+        lsa.save_state(&db).unwrap();
+        db.set_state_blob(PROJECTION_KEY, &[0u8; 10]).unwrap();
+        assert!(matches!(
+            lsa.load_state(&db),
+            Err(UmapError::CorruptCollection(_))
+        ));
+    }
+}
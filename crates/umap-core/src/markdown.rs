@@ -0,0 +1,382 @@
+use std::ops::Range;
+
+use crate::chunk::{Chunk, Chunker, Level, LineIndex, RecursiveChunker};
+use crate::error::Result;
+use crate::tokenizer::Tokenizer;
+
+enum BlockKind {
+    Heading {
+        level: usize,
+        title: String,
+    },
+    /// A fenced code block, fences included; never split
+    Code,
+    /// A paragraph, or a list with its items and continuation lines
+    Text,
+}
+
+struct Block {
+    range: Range<usize>,
+    kind: BlockKind,
+}
+
+/// An open paragraph or list while scanning lines.
+struct TextBlock {
+    start: usize,
+    end: usize,
+    lines: usize,
+    list: bool,
+    /// A list followed by a blank line, which may still continue
+    after_blank: bool,
+}
+
+/// Splits Markdown along its heading hierarchy. Blocks of a section
+/// (paragraphs, lists, fenced code) are merged up to `tokens_per_chunk` words
+/// like the recursive chunker does; code blocks are never split, even when
+/// larger than the budget. Each chunk records its section path, e.g.
+/// "Guide > Install > Linux".
+pub struct MarkdownChunker {
+    inner: RecursiveChunker,
+}
+
+impl MarkdownChunker {
+    pub fn new(tokenizer: Tokenizer, tokens_per_chunk: usize, overlap: usize) -> Self {
+        Self {
+            inner: RecursiveChunker::new(tokenizer, tokens_per_chunk, overlap),
+        }
+    }
+
+    /// Chunk one section's blocks and tag them with `section`.
+    fn emit(
+        &self,
+        input: &str,
+        lines: &LineIndex,
+        section: &Option<String>,
+        blocks: &[&Block],
+        out: &mut Vec<Chunk>,
+    ) {
+        let mut ranges = Vec::new();
+        let mut fitting = Vec::new();
+        for block in blocks {
+            let n = self.inner.words(input, &block.range);
+            if n <= self.inner.tokens_per_chunk() {
+                fitting.push((block.range.clone(), n));
+                continue;
+            }
+            ranges.extend(self.inner.merge(&fitting));
+            fitting.clear();
+            match block.kind {
+                BlockKind::Code => ranges.push(block.range.clone()),
+                _ => ranges.extend(
+                    self.inner
+                        .split(input, block.range.clone(), Level::Sentence),
+                ),
+            }
+        }
+        ranges.extend(self.inner.merge(&fitting));
+        out.extend(ranges.into_iter().map(|r| Chunk {
+            section: section.clone(),
+            ..lines.chunk(input, r.start, r.end)
+        }));
+    }
+}
+
+impl Chunker for MarkdownChunker {
+    fn name(&self) -> &str {
+        "markdown"
+    }
+
+    fn params(&self) -> serde_json::Value {
+        self.inner.params()
+    }
+
+    fn chunk(&self, input: &str) -> Result<Vec<Chunk>> {
+        let lines = LineIndex::new(input);
+        let blocks = blocks(input);
+        let mut out = Vec::new();
+        let mut path: Vec<(usize, &str)> = Vec::new();
+        let mut section: Option<String> = None;
+        let mut current: Vec<&Block> = Vec::new();
+        for block in &blocks {
+            if let BlockKind::Heading { level, title } = &block.kind {
+                self.emit(input, &lines, &section, &current, &mut out);
+                current.clear();
+                while path.last().is_some_and(|(l, _)| l >= level) {
+                    path.pop();
+                }
+                path.push((*level, title));
+                section = Some(path.iter().map(|(_, t)| *t).collect::<Vec<_>>().join(" > "));
+            }
+            current.push(block);
+        }
+        self.emit(input, &lines, &section, &current, &mut out);
+        Ok(out)
+    }
+}
+
+/// Scan `input` into headings, fenced code blocks and text blocks.
+fn blocks(input: &str) -> Vec<Block> {
+    let mut out = Vec::new();
+    let mut text: Option<TextBlock> = None;
+    // Open fence: its character, length and where the block started
+    let mut fence: Option<(char, usize, usize)> = None;
+    let flush = |text: &mut Option<TextBlock>, out: &mut Vec<Block>| {
+        if let Some(t) = text.take() {
+            out.push(Block {
+                range: t.start..t.end,
+                kind: BlockKind::Text,
+            });
+        }
+    };
+
+    let mut offset = 0usize;
+    for raw in input.split_inclusive('\n') {
+        let start = offset;
+        offset += raw.len();
+        let line = raw.trim_end_matches(['\n', '\r']);
+        let end = start + line.len();
+        let trimmed = line.trim_start();
+        let indent = line.len() - trimmed.len();
+
+        if let Some((c, len, code_start)) = fence {
+            let run = trimmed.chars().take_while(|&x| x == c).count();
+            if run >= len && trimmed[run * c.len_utf8()..].trim().is_empty() {
+                out.push(Block {
+                    range: code_start..end,
+                    kind: BlockKind::Code,
+                });
+                fence = None;
+            }
+            continue;
+        }
+        if indent <= 3 && (trimmed.starts_with("```") || trimmed.starts_with("~~~")) {
+            flush(&mut text, &mut out);
+            let c = trimmed.chars().next().unwrap_or('`');
+            fence = Some((c, trimmed.chars().take_while(|&x| x == c).count(), start));
+            continue;
+        }
+        if indent <= 3
+            && let Some((level, title)) = atx_heading(trimmed)
+        {
+            flush(&mut text, &mut out);
+            out.push(Block {
+                range: start..end,
+                kind: BlockKind::Heading {
+                    level,
+                    title: title.to_string(),
+                },
+            });
+            continue;
+        }
+        if trimmed.is_empty() {
+            match &mut text {
+                Some(t) if t.list => t.after_blank = true,
+                _ => flush(&mut text, &mut out),
+            }
+            continue;
+        }
+        // Setext heading: a single line of text underlined with = or -
+        if indent <= 3
+            && let Some(t) = &text
+            && t.lines == 1
+            && !t.list
+            && let Some(level) = setext_level(trimmed)
+        {
+            let title = input[t.start..t.end].trim().to_string();
+            out.push(Block {
+                range: t.start..end,
+                kind: BlockKind::Heading { level, title },
+            });
+            text = None;
+            continue;
+        }
+        let item = is_list_item(trimmed);
+        if let Some(t) = &mut text {
+            if !t.after_blank || item || indent >= 2 {
+                t.end = end;
+                t.lines += 1;
+                t.after_blank = false;
+                continue;
+            }
+            flush(&mut text, &mut out);
+        }
+        text = Some(TextBlock {
+            start,
+            end,
+            lines: 1,
+            list: item,
+            after_blank: false,
+        });
+    }
+
+    if let Some((_, _, code_start)) = fence {
+        // Unclosed fence: the code runs to the end of the document
+        out.push(Block {
+            range: code_start..input.trim_end().len().max(code_start),
+            kind: BlockKind::Code,
+        });
+    }
+    flush(&mut text, &mut out);
+    out
+}
+
+/// `# Title`, `## Title ##` and so on, up to six levels.
+fn atx_heading(line: &str) -> Option<(usize, &str)> {
+    let level = line.chars().take_while(|&c| c == '#').count();
+    if !(1..=6).contains(&level) {
+        return None;
+    }
+    let rest = &line[level..];
+    if !rest.is_empty() && !rest.starts_with([' ', '\t']) {
+        return None;
+    }
+    let title = rest.trim();
+    // A closing run of #s only counts after whitespace, so "C#" keeps its #
+    let without = title.trim_end_matches('#');
+    let title = if without.is_empty() || without.ends_with([' ', '\t']) {
+        without.trim_end()
+    } else {
+        title
+    };
+    Some((level, title))
+}
+
+fn setext_level(line: &str) -> Option<usize> {
+    let line = line.trim_end();
+    if !line.is_empty() && line.chars().all(|c| c == '=') {
+        Some(1)
+    } else if !line.is_empty() && line.chars().all(|c| c == '-') {
+        Some(2)
+    } else {
+        None
+    }
+}
+
+/// "- item", "* item", "+ item", "1. item" or "1) item".
+fn is_list_item(line: &str) -> bool {
+    if line.starts_with("- ") || line.starts_with("* ") || line.starts_with("+ ") {
+        return true;
+    }
+    let digits = line.chars().take_while(char::is_ascii_digit).count();
+    digits > 0 && (line[digits..].starts_with(". ") || line[digits..].starts_with(") "))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn chunker(tokens_per_chunk: usize) -> MarkdownChunker {
+        MarkdownChunker::new(Tokenizer::default(), tokens_per_chunk, 0)
+    }
+
+    /// Expected section path and text of one chunk.
+    type Section<'a> = (Option<&'a str>, &'a str);
+
+    fn sections(input: &str) -> Vec<(Option<String>, String)> {
+        chunker(100)
+            .chunk(input)
+            .unwrap()
+            .into_iter()
+            .map(|c| (c.section, c.text))
+            .collect()
+    }
+
+    #[test]
+    fn follows_the_heading_hierarchy() {
+        let cases: &[(&str, &[Section])] = &[
+            (
+                "# Guide\nIntro text.\n## Install\nRun the setup.\n",
+                &[
+                    (Some("Guide"), "# Guide\nIntro text."),
+                    (Some("Guide > Install"), "## Install\nRun the setup."),
+                ],
+            ),
+            (
+                "Guide\n=====\nIntro text.\n\nInstall\n-------\nRun the setup.",
+                &[
+                    (Some("Guide"), "Guide\n=====\nIntro text."),
+                    (Some("Guide > Install"), "Install\n-------\nRun the setup."),
+                ],
+            ),
+            (
+                "# A\n## B\nin b\n## C\nin c\n# D\nin d",
+                &[
+                    (Some("A"), "# A"),
+                    (Some("A > B"), "## B\nin b"),
+                    (Some("A > C"), "## C\nin c"),
+                    (Some("D"), "# D\nin d"),
+                ],
+            ),
+            (
+                "Preface words.\n\n# Body\nMain text.",
+                &[
+                    (None, "Preface words."),
+                    (Some("Body"), "# Body\nMain text."),
+                ],
+            ),
+            (
+                "# Code\n```sh\n# not a heading\n```\nAfter the fence.",
+                &[(
+                    Some("Code"),
+                    "# Code\n```sh\n# not a heading\n```\nAfter the fence.",
+                )],
+            ),
+            (
+                "## Using C# ##\nIt compiles.\n#hashtag stays text",
+                &[(
+                    Some("Using C#"),
+                    "## Using C# ##\nIt compiles.\n#hashtag stays text",
+                )],
+            ),
+            (
+                "- one item\n- two item\n---\nAfter the rule.",
+                &[(None, "- one item\n- two item\n---\nAfter the rule.")],
+            ),
+        ];
+        for (input, expected) in cases {
+            let expected: Vec<(Option<String>, String)> = expected
+                .iter()
+                .map(|(s, t)| (s.map(str::to_string), t.to_string()))
+                .collect();
+            assert_eq!(sections(input), expected, "input: {input:?}");
+        }
+    }
+
+    #[test]
+    fn over_budget_sections_fall_back_to_the_inner_splitter() {
+        let input = "# Notes\nOne two three four. Five six seven eight. Nine ten eleven twelve.\n\n\
+                     ```\nlet a = one two three four five six seven;\n```\n";
+        let chunker = chunker(5);
+        let chunks = chunker.chunk(input).unwrap();
+        assert!(chunks.len() > 2, "{chunks:?}");
+        for c in &chunks {
+            assert_eq!(c.section.as_deref(), Some("Notes"));
+            let words = chunker.inner.words(input, &(c.span.start..c.span.end));
+            // Code blocks are kept whole even when over budget
+            assert!(words <= 5 || c.text.starts_with("```"), "{c:?}");
+        }
+        assert!(
+            chunks
+                .iter()
+                .any(|c| c.text.starts_with("```") && c.text.ends_with("```"))
+        );
+    }
+
+    #[test]
+    fn spans_map_back_to_the_input() {
+        let inputs = [
+            "# Título\r\nTexto en español.\r\n\r\n## Más\r\nOtro párrafo aquí.\r\n",
+            "Intro\n===\n\n* a list item\n  continued\n\n* another\n\n```\nunclosed fence",
+            "   # Indented heading\nBody.\n\n    indented code stays text\n",
+        ];
+        for input in inputs {
+            for budget in [3, 100] {
+                for c in chunker(budget).chunk(input).unwrap() {
+                    assert_eq!(&input[c.span.start..c.span.end], c.text, "input: {input:?}");
+                    let line = input[..c.span.start].matches('\n').count() + 1;
+                    assert_eq!(c.span.start_line, line, "input: {input:?}");
+                }
+            }
+        }
+    }
+}
@@ -0,0 +1,218 @@
+use ndarray::Array2;
+
+use crate::error::{Result, UmapError};
+use crate::reduction::pca_project;
+
+/// A transformation applied to the search-result matrix before reduction.
+pub trait Preprocessor {
+    /// Name as written in a chain spec, e.g. `"pca:50"`.
+    fn name(&self) -> String;
+    fn apply(&self, data: &Array2<f32>) -> Result<Array2<f32>>;
+}
+
+/// Subtract the per-column mean.
+pub struct MeanCenter;
+
+impl Preprocessor for MeanCenter {
+    fn name(&self) -> String {
+        "center".to_string()
+    }
+
+    fn apply(&self, data: &Array2<f32>) -> Result<Array2<f32>> {
+        let (n, d) = data.dim();
+        let mut out = data.clone();
+        if n == 0 {
+            return Ok(out);
+        }
+        for j in 0..d {
+            let mean = (0..n).map(|i| data[(i, j)] as f64).sum::<f64>() / n as f64;
+            for i in 0..n {
+                out[(i, j)] -= mean as f32;
+            }
+        }
+        Ok(out)
+    }
+}
+
+/// Scale every row to unit L2 norm.
+pub struct L2Normalize;
+
+impl Preprocessor for L2Normalize {
+    fn name(&self) -> String {
+        "normalize".to_string()
+    }
+
+    fn apply(&self, data: &Array2<f32>) -> Result<Array2<f32>> {
+        let (n, d) = data.dim();
+        let mut out = data.clone();
+        for i in 0..n {
+            let norm = (0..d)
+                .map(|j| (data[(i, j)] as f64) * (data[(i, j)] as f64))
+                .sum::<f64>()
+                .sqrt() as f32;
+            if norm > 0.0 {
+                for j in 0..d {
+                    out[(i, j)] /= norm;
+                }
+            }
+        }
+        Ok(out)
+    }
+}
+
+/// Center every column and scale it to unit variance (z-score).
+pub struct Standardize;
+
+impl Preprocessor for Standardize {
+    fn name(&self) -> String {
+        "standardize".to_string()
+    }
+
+    fn apply(&self, data: &Array2<f32>) -> Result<Array2<f32>> {
+        let (n, d) = data.dim();
+        let mut out = data.clone();
+        if n == 0 {
+            return Ok(out);
+        }
+        for j in 0..d {
+            let mean = (0..n).map(|i| data[(i, j)] as f64).sum::<f64>() / n as f64;
+            let var = (0..n)
+                .map(|i| {
+                    let v = data[(i, j)] as f64 - mean;
+                    v * v
+                })
+                .sum::<f64>()
+                / n as f64;
+            let std = var.sqrt();
+            for i in 0..n {
+                let centered = data[(i, j)] as f64 - mean;
+                // Constant columns carry no information; leave them at zero
+                out[(i, j)] = if std > 1e-12 {
+                    (centered / std) as f32
+                } else {
+                    0.0
+                };
+            }
+        }
+        Ok(out)
+    }
+}
+
+/// TF-IDF style column reweighting: columns that are non-zero in many rows are
+/// damped by `ln((1 + n) / (1 + df)) + 1`.
+pub struct IdfReweight;
+
+impl Preprocessor for IdfReweight {
+    fn name(&self) -> String {
+        "idf".to_string()
+    }
+
+    fn apply(&self, data: &Array2<f32>) -> Result<Array2<f32>> {
+        let (n, d) = data.dim();
+        let mut out = data.clone();
+        for j in 0..d {
+            let df = (0..n).filter(|&i| data[(i, j)] != 0.0).count();
+            let idf = (((1 + n) as f32) / ((1 + df) as f32)).ln() + 1.0;
+            for i in 0..n {
+                out[(i, j)] *= idf;
+            }
+        }
+        Ok(out)
+    }
+}
+
+/// Project onto the first `n_components` principal components, e.g. to
+/// denoise high-dimensional vectors before running UMAP.
+pub struct PcaPreprocess {
+    pub n_components: usize,
+}
+
+impl Preprocessor for PcaPreprocess {
+    fn name(&self) -> String {
+        format!("pca:{}", self.n_components)
+    }
+
+    fn apply(&self, data: &Array2<f32>) -> Result<Array2<f32>> {
+        let (n, d) = data.dim();
+        // PCA cannot produce more components than samples or features
+        let comps = self.n_components.min(n).min(d);
+        if comps == 0 {
+            return Ok(data.clone());
+        }
+        pca_project(data, comps)
+    }
+}
+
+/// An ordered list of preprocessing steps.
+#[derive(Default)]
+pub struct PreprocessChain {
+    steps: Vec<Box<dyn Preprocessor>>,
+}
+
+impl PreprocessChain {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn push(mut self, step: Box<dyn Preprocessor>) -> Self {
+        self.steps.push(step);
+        self
+    }
+
+    /// Parse a comma-separated spec such as `"center,normalize,pca:50"`.
+    /// An empty spec (or `"none"`) yields an empty chain.
+    pub fn parse(spec: &str) -> Result<Self> {
+        let mut chain = Self::new();
+        for part in spec.split(',').map(|s| s.trim().to_lowercase()) {
+            if part.is_empty() || part == "none" {
+                continue;
+            }
+            let (name, arg) = match part.split_once(':') {
+                Some((n, a)) => (n.to_string(), Some(a.to_string())),
+                None => (part.clone(), None),
+            };
+            let step: Box<dyn Preprocessor> = match (name.as_str(), arg) {
+                ("center", None) => Box::new(MeanCenter),
+                ("normalize" | "l2", None) => Box::new(L2Normalize),
+                ("standardize" | "zscore", None) => Box::new(Standardize),
+                ("idf", None) => Box::new(IdfReweight),
+                ("pca", Some(a)) => {
+                    let n_components = a.parse::<usize>().map_err(|_| {
+                        UmapError::invalid(format!("invalid pca component count: {a}"))
+                    })?;
+                    if n_components == 0 {
+                        return Err(UmapError::invalid("pca component count must be > 0"));
+                    }
+                    Box::new(PcaPreprocess { n_components })
+                }
+                ("pca", None) => {
+                    return Err(UmapError::invalid("pca step needs a size, e.g. pca:50"));
+                }
+                _ => {
+                    return Err(UmapError::invalid(format!(
+                        "unknown preprocessing step: {part}"
+                    )));
+                }
+            };
+            chain.steps.push(step);
+        }
+        Ok(chain)
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.steps.is_empty()
+    }
+
+    /// Step names in application order, suitable for echoing back to clients.
+    pub fn names(&self) -> Vec<String> {
+        self.steps.iter().map(|s| s.name()).collect()
+    }
+
+    pub fn apply(&self, data: &Array2<f32>) -> Result<Array2<f32>> {
+        let mut cur = data.clone();
+        for step in &self.steps {
+            cur = step.apply(&cur)?;
+        }
+        Ok(cur)
+    }
+}
@@ -0,0 +1,211 @@
+use std::str::FromStr;
+
+use serde::{Deserialize, Serialize};
+
+use crate::error::{Result, UmapError};
+
+/// Encoding of the vectors stored in a collection.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Quantization {
+    /// Raw little-endian f32, 4 bytes per component
+    #[default]
+    None,
+    /// One signed byte per component plus a per-vector f32 scale
+    Int8,
+    /// One sign bit per component, scored by Hamming distance
+    Binary,
+}
+
+impl FromStr for Quantization {
+    type Err = UmapError;
+
+    fn from_str(s: &str) -> Result<Self> {
+        match s.to_lowercase().as_str() {
+            "none" | "f32" => Ok(Quantization::None),
+            "int8" | "i8" => Ok(Quantization::Int8),
+            "binary" | "bit" => Ok(Quantization::Binary),
+            other => Err(UmapError::invalid(format!(
+                "unknown quantization: {other} (expected none, int8 or binary)"
+            ))),
+        }
+    }
+}
+
+/// How a collection stores its vectors, recorded when the first chunk is
+/// ingested.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct VectorStorage {
+    pub quantization: Quantization,
+    /// Also keep the f32 vectors, so search can rescore the best quantized
+    /// candidates at full precision. Costs the disk space quantization saves,
+    /// but the scan still only reads the compact codes.
+    #[serde(default)]
+    pub keep_full_precision: bool,
+}
+
+impl Quantization {
+    /// Bytes of one encoded vector of `dim` components.
+    pub fn encoded_len(self, dim: usize) -> usize {
+        match self {
+            Quantization::None => dim * 4,
+            Quantization::Int8 => 4 + dim,
+            Quantization::Binary => dim.div_ceil(8),
+        }
+    }
+
+    pub fn encode(self, vector: &[f32]) -> Vec<u8> {
+        match self {
+            Quantization::None => encode_f32(vector),
+            Quantization::Int8 => {
+                let max = vector.iter().fold(0f32, |m, v| m.max(v.abs()));
+                let scale = if max > 0.0 { max / 127.0 } else { 0.0 };
+                let mut out = Vec::with_capacity(4 + vector.len());
+                out.extend_from_slice(&scale.to_le_bytes());
+                for v in vector {
+                    let q = if scale > 0.0 {
+                        (v / scale).round().clamp(-127.0, 127.0) as i8
+                    } else {
+                        0
+                    };
+                    out.push(q as u8);
+                }
+                out
+            }
+            Quantization::Binary => {
+                let mut out = vec![0u8; vector.len().div_ceil(8)];
+                for (i, v) in vector.iter().enumerate() {
+                    if *v > 0.0 {
+                        out[i / 8] |= 1 << (i % 8);
+                    }
+                }
+                out
+            }
+        }
+    }
+
+    /// Approximate f32 vector for an encoded one. Binary codes decode to
+    /// `±1/sqrt(dim)`, which keeps their cosines equal to the Hamming score.
+    /// The caller has checked the length against [`Quantization::encoded_len`].
+    pub fn decode(self, dim: usize, blob: &[u8]) -> Vec<f32> {
+        match self {
+            Quantization::None => decode_f32(blob),
+            Quantization::Int8 => {
+                let scale = f32::from_le_bytes([blob[0], blob[1], blob[2], blob[3]]);
+                blob[4..].iter().map(|&b| b as i8 as f32 * scale).collect()
+            }
+            Quantization::Binary => {
+                let unit = 1.0 / (dim.max(1) as f32).sqrt();
+                (0..dim)
+                    .map(|i| {
+                        if blob[i / 8] & (1 << (i % 8)) != 0 {
+                            unit
+                        } else {
+                            -unit
+                        }
+                    })
+                    .collect()
+            }
+        }
+    }
+}
+
+pub(crate) fn encode_f32(vector: &[f32]) -> Vec<u8> {
+    let mut blob = Vec::with_capacity(vector.len() * 4);
+    for v in vector {
+        blob.extend_from_slice(&v.to_le_bytes());
+    }
+    blob
+}
+
+pub(crate) fn decode_f32(blob: &[u8]) -> Vec<f32> {
+    let mut out = Vec::with_capacity(blob.len() / 4);
+    extend_f32(&mut out, blob);
+    out
+}
+
+/// Appends the little-endian f32s in `blob` to `out`, reusing its buffer.
+pub(crate) fn extend_f32(out: &mut Vec<f32>, blob: &[u8]) {
+    out.extend(
+        blob.chunks_exact(4)
+            .map(|b| f32::from_le_bytes([b[0], b[1], b[2], b[3]])),
+    );
+}
+
+/// A query encoded once so it can be scored against stored codes without
+/// decoding them.
+pub(crate) enum QuantizedQuery {
+    Int8 { codes: Vec<u8>, norm: f32 },
+    Binary { bits: Vec<u8>, dim: usize },
+}
+
+impl QuantizedQuery {
+    /// `None` for unquantized collections, which are scored exactly.
+    pub(crate) fn new(quantization: Quantization, query: &[f32]) -> Option<Self> {
+        match quantization {
+            Quantization::None => None,
+            Quantization::Int8 => {
+                let codes = quantization.encode(query)[4..].to_vec();
+                let norm = int8_norm(&codes);
+                Some(QuantizedQuery::Int8 { codes, norm })
+            }
+            Quantization::Binary => Some(QuantizedQuery::Binary {
+                bits: quantization.encode(query),
+                dim: query.len(),
+            }),
+        }
+    }
+
+    /// Estimated cosine similarity with a stored code of the same encoding.
+    pub(crate) fn score(&self, blob: &[u8]) -> f32 {
+        match self {
+            QuantizedQuery::Int8 { codes, norm } => {
+                // The per-vector scales cancel out of the cosine
+                let other = &blob[4..];
+                let dot = int8_dot(codes, other);
+                let denom = norm * int8_norm(other);
+                if denom == 0.0 {
+                    0.0
+                } else {
+                    dot as f32 / denom
+                }
+            }
+            QuantizedQuery::Binary { bits, dim } => {
+                // Fraction of agreeing signs, mapped onto -1..=1
+                let differing = hamming(bits, blob);
+                1.0 - 2.0 * differing as f32 / (*dim).max(1) as f32
+            }
+        }
+    }
+}
+
+// Codes are stored as bytes; reinterpret them as i8 and accumulate in i32
+fn int8_dot(a: &[u8], b: &[u8]) -> i32 {
+    a.iter()
+        .zip(b)
+        .map(|(&x, &y)| (x as i8) as i32 * (y as i8) as i32)
+        .sum()
+}
+
+fn int8_norm(codes: &[u8]) -> f32 {
+    (int8_dot(codes, codes) as f32).sqrt()
+}
+
+/// Number of differing bits, a machine word at a time.
+pub fn hamming(a: &[u8], b: &[u8]) -> u32 {
+    let mut words_a = a.chunks_exact(8);
+    let mut words_b = b.chunks_exact(8);
+    let mut total: u32 = words_a
+        .by_ref()
+        .zip(words_b.by_ref())
+        .map(|(x, y)| {
+            let x = u64::from_le_bytes(x.try_into().unwrap());
+            let y = u64::from_le_bytes(y.try_into().unwrap());
+            (x ^ y).count_ones()
+        })
+        .sum();
+    for (x, y) in words_a.remainder().iter().zip(words_b.remainder()) {
+        total += (x ^ y).count_ones();
+    }
+    total
+}
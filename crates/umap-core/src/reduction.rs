@@ -1,9 +1,13 @@
-use anyhow::{Result, anyhow};
 use linfa::dataset::DatasetBase;
 use linfa::prelude::{Fit, Transformer};
+use linfa_linalg::eigh::Eigh;
 use linfa_reduction::Pca;
 use ndarray::Array2;
 use rand::prelude::*;
+use std::cmp::Ordering;
+use std::collections::BinaryHeap;
+
+use crate::error::{Result, UmapError};
 
 pub trait Reducer {
     fn reduce(&self, data: &Array2<f32>, dims: usize) -> Result<Array2<f32>>;
@@ -14,36 +18,298 @@ pub struct PcaReducer;
 impl Reducer for PcaReducer {
     fn reduce(&self, data: &Array2<f32>, dims: usize) -> Result<Array2<f32>> {
         if !(1..=3).contains(&dims) {
-            return Err(anyhow!("dims must be 1..=3"));
+            return Err(UmapError::invalid("dims must be 1..=3"));
+        }
+        pca_project(data, dims)
+    }
+}
+
+/// Project `data` onto its first `dims` principal components.
+///
+/// Unlike [`PcaReducer`] this accepts any number of components, which makes it
+/// usable as a preprocessing step ahead of UMAP.
+pub fn pca_project(data: &Array2<f32>, dims: usize) -> Result<Array2<f32>> {
+    // PCA expects f64 internally; convert
+    let n = data.nrows();
+    let d = data.ncols();
+    let mut data64 = Array2::<f64>::zeros((n, d));
+    for i in 0..n {
+        for j in 0..d {
+            data64[(i, j)] = data[(i, j)] as f64;
+        }
+    }
+    let ds = DatasetBase::from(data64);
+    let model = Pca::params(dims)
+        .fit(&ds)
+        .map_err(|e| UmapError::Numerical(format!("PCA fit failed: {e}")))?;
+    let transformed = model.transform(ds);
+    let x = transformed.records;
+    let mut out = Array2::<f32>::zeros((n, dims));
+    for i in 0..n {
+        for j in 0..dims {
+            out[(i, j)] = x[(i, j)] as f32;
+        }
+    }
+    Ok(out)
+}
+
+pub fn reduce_default(data: &Array2<f32>, dims: usize) -> Result<Array2<f32>> {
+    let reducer = PcaReducer;
+    reducer.reduce(data, dims)
+}
+
+/// Pick a reducer by the method name used in the API (`pca`, `umap`, `random`,
+/// `sparse_random`, `mds`, `isomap`).
+pub fn reducer_by_name(method: &str, params: &UmapParams) -> Result<Box<dyn Reducer>> {
+    let reducer: Box<dyn Reducer> = match method.to_lowercase().as_str() {
+        "pca" => Box::new(PcaReducer),
+        "umap" => Box::new(UmapReducer {
+            params: params.clone(),
+        }),
+        "random" | "gaussian_random" => Box::new(RandomProjectionReducer {
+            kind: RandomProjectionKind::Gaussian,
+            seed: params.random_state,
+        }),
+        "sparse_random" => Box::new(RandomProjectionReducer {
+            kind: RandomProjectionKind::Sparse,
+            seed: params.random_state,
+        }),
+        "mds" => Box::new(MdsReducer),
+        "isomap" => Box::new(IsomapReducer {
+            n_neighbors: params.n_neighbors,
+        }),
+        other => {
+            return Err(UmapError::invalid(format!(
+                "unknown reduction method: {other}"
+            )));
+        }
+    };
+    Ok(reducer)
+}
+
+pub struct UmapReducer {
+    pub params: UmapParams,
+}
+
+impl Reducer for UmapReducer {
+    fn reduce(&self, data: &Array2<f32>, dims: usize) -> Result<Array2<f32>> {
+        umap_reduce_cosine(data, dims, &self.params)
+    }
+}
+
+// ----------------- Random projection (no structure learned) -----------------
+
+#[derive(Debug, Clone, Copy)]
+pub enum RandomProjectionKind {
+    /// Dense N(0, 1/dims) entries
+    Gaussian,
+    /// Achlioptas sparse entries: sqrt(3/dims) * {+1, 0, -1} with p = {1/6, 2/3, 1/6}
+    Sparse,
+}
+
+pub struct RandomProjectionReducer {
+    pub kind: RandomProjectionKind,
+    pub seed: u64,
+}
+
+impl Reducer for RandomProjectionReducer {
+    fn reduce(&self, data: &Array2<f32>, dims: usize) -> Result<Array2<f32>> {
+        if !(1..=3).contains(&dims) {
+            return Err(UmapError::invalid("dims must be 1..=3"));
         }
-        // PCA expects f64 internally; convert
-        let n = data.nrows();
         let d = data.ncols();
-        let mut data64 = Array2::<f64>::zeros((n, d));
+        let mut rng = StdRng::seed_from_u64(self.seed);
+        let mut proj = Array2::<f32>::zeros((d, dims));
+        match self.kind {
+            RandomProjectionKind::Gaussian => {
+                let scale = 1.0 / (dims as f32).sqrt();
+                for v in proj.iter_mut() {
+                    *v = standard_normal(&mut rng) * scale;
+                }
+            }
+            RandomProjectionKind::Sparse => {
+                let scale = (3.0 / dims as f32).sqrt();
+                for v in proj.iter_mut() {
+                    let r = rng.r#gen::<f32>();
+                    *v = if r < 1.0 / 6.0 {
+                        scale
+                    } else if r < 2.0 / 6.0 {
+                        -scale
+                    } else {
+                        0.0
+                    };
+                }
+            }
+        }
+        Ok(data.dot(&proj))
+    }
+}
+
+fn standard_normal(rng: &mut StdRng) -> f32 {
+    // Box–Muller transform; avoids pulling in rand_distr for one distribution
+    let u1 = rng.r#gen::<f32>().max(f32::MIN_POSITIVE);
+    let u2 = rng.r#gen::<f32>();
+    (-2.0 * u1.ln()).sqrt() * (2.0 * std::f32::consts::PI * u2).cos()
+}
+
+// ----------------- Classical MDS and Isomap -----------------
+
+/// Classical (Torgerson) MDS on Euclidean distances. With Euclidean input this
+/// recovers the same layout as PCA up to sign, which is the point of showing it.
+pub struct MdsReducer;
+
+impl Reducer for MdsReducer {
+    fn reduce(&self, data: &Array2<f32>, dims: usize) -> Result<Array2<f32>> {
+        if !(1..=3).contains(&dims) {
+            return Err(UmapError::invalid("dims must be 1..=3"));
+        }
+        let n = data.nrows();
+        let mut d2 = Array2::<f64>::zeros((n, n));
+        for i in 0..n {
+            for j in (i + 1)..n {
+                let v = squared_euclidean(data, i, j);
+                d2[(i, j)] = v;
+                d2[(j, i)] = v;
+            }
+        }
+        classical_mds(&d2, dims)
+    }
+}
+
+/// Isomap: classical MDS on geodesic distances through the kNN graph, so points
+/// are placed by distance *along* the manifold rather than straight-line.
+pub struct IsomapReducer {
+    pub n_neighbors: usize,
+}
+
+impl Reducer for IsomapReducer {
+    fn reduce(&self, data: &Array2<f32>, dims: usize) -> Result<Array2<f32>> {
+        if !(1..=3).contains(&dims) {
+            return Err(UmapError::invalid("dims must be 1..=3"));
+        }
+        let n = data.nrows();
+        if n <= 2 {
+            return Ok(Array2::<f32>::zeros((n, dims)));
+        }
+        let k = self.n_neighbors.min(n - 1).max(1);
+
+        // Symmetric kNN adjacency list with Euclidean edge lengths
+        let mut adj: Vec<Vec<(usize, f64)>> = vec![Vec::new(); n];
         for i in 0..n {
-            for j in 0..d {
-                data64[(i, j)] = data[(i, j)] as f64;
+            let mut all: Vec<(usize, f64)> = (0..n)
+                .filter(|&j| j != i)
+                .map(|j| (j, squared_euclidean(data, i, j).sqrt()))
+                .collect();
+            all.sort_by(|a, b| a.1.partial_cmp(&b.1).unwrap_or(Ordering::Equal));
+            for &(j, w) in all.iter().take(k) {
+                adj[i].push((j, w));
+                adj[j].push((i, w));
             }
         }
-        let ds = DatasetBase::from(data64);
-        let model = Pca::params(dims)
-            .fit(&ds)
-            .map_err(|e| anyhow!("PCA fit failed: {e}"))?;
-        let transformed = model.transform(ds);
-        let x = transformed.records;
-        let mut out = Array2::<f32>::zeros((n, dims));
+
+        let mut geo = Array2::<f64>::from_elem((n, n), f64::INFINITY);
+        for src in 0..n {
+            let dist = dijkstra(&adj, src);
+            for (j, d) in dist.into_iter().enumerate() {
+                geo[(src, j)] = d;
+            }
+        }
+        // Disconnected components: place them just beyond the farthest reachable pair
+        let max_finite = geo
+            .iter()
+            .copied()
+            .filter(|d| d.is_finite())
+            .fold(0.0f64, f64::max);
+        let mut d2 = Array2::<f64>::zeros((n, n));
         for i in 0..n {
-            for j in 0..dims {
-                out[(i, j)] = x[(i, j)] as f32;
+            for j in 0..n {
+                let d = geo[(i, j)].min(geo[(j, i)]);
+                let d = if d.is_finite() { d } else { max_finite * 1.5 };
+                d2[(i, j)] = d * d;
             }
         }
-        Ok(out)
+        classical_mds(&d2, dims)
     }
 }
 
-pub fn reduce_default(data: &Array2<f32>, dims: usize) -> Result<Array2<f32>> {
-    let reducer = PcaReducer;
-    reducer.reduce(data, dims)
+fn squared_euclidean(data: &Array2<f32>, i: usize, j: usize) -> f64 {
+    let mut s = 0.0f64;
+    for c in 0..data.ncols() {
+        let v = (data[(i, c)] - data[(j, c)]) as f64;
+        s += v * v;
+    }
+    s
+}
+
+#[derive(PartialEq)]
+struct HeapItem(f64, usize);
+
+impl Eq for HeapItem {}
+
+impl Ord for HeapItem {
+    fn cmp(&self, other: &Self) -> Ordering {
+        // Reversed so BinaryHeap pops the smallest distance first
+        other.0.partial_cmp(&self.0).unwrap_or(Ordering::Equal)
+    }
+}
+
+impl PartialOrd for HeapItem {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+fn dijkstra(adj: &[Vec<(usize, f64)>], src: usize) -> Vec<f64> {
+    let mut dist = vec![f64::INFINITY; adj.len()];
+    dist[src] = 0.0;
+    let mut heap = BinaryHeap::new();
+    heap.push(HeapItem(0.0, src));
+    while let Some(HeapItem(d, u)) = heap.pop() {
+        if d > dist[u] {
+            continue;
+        }
+        for &(v, w) in &adj[u] {
+            let nd = d + w;
+            if nd < dist[v] {
+                dist[v] = nd;
+                heap.push(HeapItem(nd, v));
+            }
+        }
+    }
+    dist
+}
+
+/// Embed points given their squared pairwise distances: double-center into a
+/// Gram matrix and scale its top eigenvectors by sqrt(eigenvalue).
+fn classical_mds(d2: &Array2<f64>, dims: usize) -> Result<Array2<f32>> {
+    let n = d2.nrows();
+    if n == 0 {
+        return Ok(Array2::<f32>::zeros((0, dims)));
+    }
+    let row_means: Vec<f64> = (0..n)
+        .map(|i| (0..n).map(|j| d2[(i, j)]).sum::<f64>() / n as f64)
+        .collect();
+    let grand_mean = row_means.iter().sum::<f64>() / n as f64;
+    let mut b = Array2::<f64>::zeros((n, n));
+    for i in 0..n {
+        for j in 0..n {
+            b[(i, j)] = -0.5 * (d2[(i, j)] - row_means[i] - row_means[j] + grand_mean);
+        }
+    }
+    let (vals, vecs) = b
+        .eigh()
+        .map_err(|e| UmapError::Numerical(format!("MDS eigendecomposition failed: {e}")))?;
+    let mut order: Vec<usize> = (0..n).collect();
+    order.sort_by(|&a, &b| vals[b].partial_cmp(&vals[a]).unwrap_or(Ordering::Equal));
+    let mut out = Array2::<f32>::zeros((n, dims));
+    for (c, &idx) in order.iter().take(dims).enumerate() {
+        // Negative eigenvalues come from non-Euclidean (e.g. geodesic) input; drop them
+        let scale = vals[idx].max(0.0).sqrt();
+        for i in 0..n {
+            out[(i, c)] = (vecs[(i, idx)] * scale) as f32;
+        }
+    }
+    Ok(out)
 }
 
 // ----------------- Minimal UMAP implementation (educational) -----------------
@@ -83,7 +349,7 @@ pub fn umap_reduce_cosine(
     params: &UmapParams,
 ) -> Result<Array2<f32>> {
     if !(2..=3).contains(&dims) {
-        return Err(anyhow!("dims must be 2 or 3"));
+        return Err(UmapError::invalid("dims must be 2 or 3"));
     }
     let n = data.nrows();
     if n == 0 {
@@ -257,8 +523,8 @@ fn symmetrize(
     let mut map: HashMap<(usize, usize), f32> = HashMap::new();
     for (r, c, v) in rows
         .into_iter()
-        .zip(cols.into_iter())
-        .zip(vals.into_iter())
+        .zip(cols)
+        .zip(vals)
         .map(|((r, c), v)| (r, c, v))
     {
         let key = (r, c);
@@ -0,0 +1,247 @@
+use std::collections::HashMap;
+use std::str::FromStr;
+use std::sync::{Mutex, OnceLock};
+use std::thread;
+use std::time::Duration;
+
+use serde::{Deserialize, Serialize};
+
+use crate::embedding::{Embedder, EmbedderConfig};
+use crate::error::{Result, UmapError};
+
+/// Environment variable holding a bearer token for the embedding service. It is
+/// read at startup and never stored with the collection.
+pub const API_KEY_ENV: &str = "UMAP_EMBEDDING_API_KEY";
+
+// First retry waits this long, doubling on each further attempt
+const BACKOFF_BASE_MS: u64 = 250;
+
+/// Wire protocol spoken by the embedding server.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum RemoteApi {
+    /// `POST /v1/embeddings` with a batch of inputs (OpenAI, vLLM, LocalAI, ...)
+    #[default]
+    OpenAi,
+    /// `POST /api/embeddings`, one prompt per request (Ollama)
+    Ollama,
+}
+
+impl FromStr for RemoteApi {
+    type Err = UmapError;
+
+    fn from_str(s: &str) -> Result<Self> {
+        match s.to_lowercase().as_str() {
+            "openai" => Ok(RemoteApi::OpenAi),
+            "ollama" => Ok(RemoteApi::Ollama),
+            other => Err(UmapError::invalid(format!(
+                "unknown embedding api: {other} (expected openai or ollama)"
+            ))),
+        }
+    }
+}
+
+#[derive(Deserialize)]
+struct OpenAiResponse {
+    data: Vec<OpenAiEmbedding>,
+}
+
+#[derive(Deserialize)]
+struct OpenAiEmbedding {
+    index: usize,
+    embedding: Vec<f32>,
+}
+
+#[derive(Deserialize)]
+struct OllamaResponse {
+    embedding: Vec<f32>,
+}
+
+/// Embeddings from an HTTP embedding service. The vector size is whatever the
+/// model returns: taken from the collection when it already records one,
+/// otherwise probed once per endpoint and model on first use.
+pub struct RemoteEmbedder {
+    api: RemoteApi,
+    endpoint: String,
+    model: String,
+    batch_size: usize,
+    timeout_secs: u64,
+    max_retries: u32,
+    api_key: Option<String>,
+    agent: ureq::Agent,
+    dim: usize,
+}
+
+impl RemoteEmbedder {
+    /// `dim` is the model's known output size, or 0 to ask the server.
+    pub fn open(
+        api: RemoteApi,
+        endpoint: &str,
+        model: &str,
+        dim: usize,
+        batch_size: usize,
+        timeout_secs: u64,
+        max_retries: u32,
+    ) -> Result<Self> {
+        let mut embedder = Self {
+            api,
+            endpoint: endpoint.trim_end_matches('/').to_string(),
+            model: model.to_string(),
+            batch_size: batch_size.max(1),
+            timeout_secs,
+            max_retries,
+            api_key: std::env::var(API_KEY_ENV).ok().filter(|k| !k.is_empty()),
+            agent: ureq::AgentBuilder::new()
+                .timeout(Duration::from_secs(timeout_secs.max(1)))
+                .build(),
+            dim,
+        };
+        if dim == 0 {
+            embedder.dim = embedder.probe_dim()?;
+        }
+        Ok(embedder)
+    }
+
+    /// Ask the server for one embedding to learn the model's output size.
+    /// Cached so servers building an embedder per request only pay for it once.
+    fn probe_dim(&self) -> Result<usize> {
+        static DIMS: OnceLock<Mutex<HashMap<(String, String), usize>>> = OnceLock::new();
+        let dims = DIMS.get_or_init(|| Mutex::new(HashMap::new()));
+        let key = (self.url(), self.model.clone());
+        if let Some(&d) = dims.lock().unwrap_or_else(|e| e.into_inner()).get(&key) {
+            return Ok(d);
+        }
+        let d = self
+            .request(&["dimension probe"])?
+            .pop()
+            .map(|v| v.len())
+            .filter(|&d| d > 0)
+            .ok_or_else(|| UmapError::Remote("server returned an empty embedding".into()))?;
+        dims.lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .insert(key, d);
+        Ok(d)
+    }
+
+    fn url(&self) -> String {
+        match self.api {
+            // Accept base URLs given with or without the /v1 prefix
+            RemoteApi::OpenAi if self.endpoint.ends_with("/v1") => {
+                format!("{}/embeddings", self.endpoint)
+            }
+            RemoteApi::OpenAi => format!("{}/v1/embeddings", self.endpoint),
+            RemoteApi::Ollama => format!("{}/api/embeddings", self.endpoint),
+        }
+    }
+
+    /// Embed one request's worth of texts: a whole batch for OpenAI-style
+    /// servers, a single text for Ollama.
+    fn request(&self, texts: &[&str]) -> Result<Vec<Vec<f32>>> {
+        match self.api {
+            RemoteApi::OpenAi => {
+                let body = serde_json::json!({ "model": self.model, "input": texts });
+                let resp: OpenAiResponse = self.post_json(&body)?;
+                if resp.data.len() != texts.len() {
+                    return Err(UmapError::Remote(format!(
+                        "asked for {} embeddings, got {}",
+                        texts.len(),
+                        resp.data.len()
+                    )));
+                }
+                let mut data = resp.data;
+                data.sort_by_key(|d| d.index);
+                Ok(data.into_iter().map(|d| d.embedding).collect())
+            }
+            RemoteApi::Ollama => texts
+                .iter()
+                .map(|t| {
+                    let body = serde_json::json!({ "model": self.model, "prompt": t });
+                    let resp: OllamaResponse = self.post_json(&body)?;
+                    Ok(resp.embedding)
+                })
+                .collect(),
+        }
+    }
+
+    /// POST with retries on transport errors, 429 and 5xx responses.
+    fn post_json<T: serde::de::DeserializeOwned>(&self, body: &serde_json::Value) -> Result<T> {
+        let url = self.url();
+        let mut attempt = 0;
+        loop {
+            let mut req = self.agent.post(&url);
+            if let Some(key) = &self.api_key {
+                req = req.set("Authorization", &format!("Bearer {key}"));
+            }
+            let err = match req.send_json(body.clone()) {
+                Ok(resp) => {
+                    return resp
+                        .into_json::<T>()
+                        .map_err(|e| UmapError::Remote(format!("bad response from {url}: {e}")));
+                }
+                Err(ureq::Error::Status(code, resp)) => {
+                    let detail = resp.into_string().unwrap_or_default();
+                    let err = UmapError::Remote(format!("{url} returned {code}: {detail}"));
+                    if code != 429 && code < 500 {
+                        return Err(err);
+                    }
+                    err
+                }
+                // Transport errors already name the URL
+                Err(e) => UmapError::Remote(e.to_string()),
+            };
+            if attempt >= self.max_retries {
+                return Err(err);
+            }
+            thread::sleep(Duration::from_millis(BACKOFF_BASE_MS << attempt.min(6)));
+            attempt += 1;
+        }
+    }
+}
+
+impl Embedder for RemoteEmbedder {
+    fn name(&self) -> &str {
+        "remote"
+    }
+
+    fn dim(&self) -> usize {
+        self.dim
+    }
+
+    fn params(&self) -> serde_json::Value {
+        serde_json::json!({
+            "api": self.api,
+            "endpoint": self.endpoint,
+            "model": self.model,
+        })
+    }
+
+    fn config(&self) -> EmbedderConfig {
+        EmbedderConfig {
+            name: "remote".to_string(),
+            dim: self.dim,
+            api: self.api,
+            endpoint: Some(self.endpoint.clone()),
+            model: Some(self.model.clone()),
+            batch_size: self.batch_size,
+            timeout_secs: self.timeout_secs,
+            max_retries: self.max_retries,
+            ..EmbedderConfig::default()
+        }
+    }
+
+    fn embed_batch(&self, texts: &[&str]) -> Result<Vec<Vec<f32>>> {
+        let mut out = Vec::with_capacity(texts.len());
+        for batch in texts.chunks(self.batch_size) {
+            for v in self.request(batch)? {
+                if v.len() != self.dim {
+                    return Err(UmapError::DimensionMismatch {
+                        expected: self.dim,
+                        actual: v.len(),
+                    });
+                }
+                out.push(v);
+            }
+        }
+        Ok(out)
+    }
+}
@@ -0,0 +1,110 @@
+use wide::f32x8;
+
+use crate::error::{Result, UmapError};
+
+const LANES: usize = 8;
+
+fn lanes(chunk: &[f32]) -> f32x8 {
+    f32x8::from(<[f32; LANES]>::try_from(chunk).unwrap())
+}
+
+/// Dot product, eight lanes at a time.
+pub fn dot(a: &[f32], b: &[f32]) -> f32 {
+    debug_assert_eq!(a.len(), b.len(), "dot on unequal lengths");
+    let mut acc = f32x8::ZERO;
+    let (ca, cb) = (a.chunks_exact(LANES), b.chunks_exact(LANES));
+    let (ra, rb) = (ca.remainder(), cb.remainder());
+    for (x, y) in ca.zip(cb) {
+        acc = lanes(x).mul_add(lanes(y), acc);
+    }
+    acc.reduce_add() + ra.iter().zip(rb).map(|(x, y)| x * y).sum::<f32>()
+}
+
+pub fn norm(a: &[f32]) -> f32 {
+    dot(a, a).sqrt()
+}
+
+/// Vectors of one dimension stored back to back, with their inverse norms
+/// computed once on insert. Scoring a query is then one dot product per row.
+pub struct CorpusMatrix {
+    dim: usize,
+    data: Vec<f32>,
+    inv_norms: Vec<f32>,
+}
+
+impl CorpusMatrix {
+    pub fn new(dim: usize) -> Self {
+        Self {
+            dim,
+            data: Vec::new(),
+            inv_norms: Vec::new(),
+        }
+    }
+
+    pub fn with_capacity(dim: usize, rows: usize) -> Self {
+        Self {
+            dim,
+            data: Vec::with_capacity(dim * rows),
+            inv_norms: Vec::with_capacity(rows),
+        }
+    }
+
+    pub fn dim(&self) -> usize {
+        self.dim
+    }
+
+    pub fn len(&self) -> usize {
+        self.inv_norms.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.inv_norms.is_empty()
+    }
+
+    pub fn push(&mut self, row: &[f32]) -> Result<()> {
+        self.push_with_norm(row, norm(row))
+    }
+
+    /// Like [`CorpusMatrix::push`], with the row's norm already known (as
+    /// stored alongside it in a collection).
+    pub fn push_with_norm(&mut self, row: &[f32], row_norm: f32) -> Result<()> {
+        if row.len() != self.dim {
+            return Err(UmapError::DimensionMismatch {
+                expected: self.dim,
+                actual: row.len(),
+            });
+        }
+        self.data.extend_from_slice(row);
+        self.inv_norms.push(inverse(row_norm));
+        Ok(())
+    }
+
+    pub fn row(&self, i: usize) -> &[f32] {
+        &self.data[i * self.dim..(i + 1) * self.dim]
+    }
+
+    /// Cosine similarity of `query` with every row, in row order.
+    pub fn cosine_scores(&self, query: &[f32]) -> Result<Vec<f32>> {
+        if query.len() != self.dim {
+            return Err(UmapError::DimensionMismatch {
+                expected: self.dim,
+                actual: query.len(),
+            });
+        }
+        let q_inv = inverse(norm(query));
+        if self.dim == 0 {
+            return Ok(vec![0.0; self.len()]);
+        }
+        Ok(self
+            .data
+            .chunks_exact(self.dim)
+            .zip(&self.inv_norms)
+            .map(|(row, inv)| dot(row, query) * inv * q_inv)
+            .collect())
+    }
+}
+
+// Zero vectors score 0 against everything, as with `cosine_similarity`
+fn inverse(norm: f32) -> f32 {
+    if norm > 1e-8 { 1.0 / norm } else { 0.0 }
+}
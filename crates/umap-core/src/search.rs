@@ -1,6 +1,14 @@
-use anyhow::Result;
+use std::cmp::Ordering;
+use std::collections::HashMap;
 
-use crate::{db::Db, embedding::cosine_similarity, types::ChunkRecord};
+use crate::{
+    db::Db,
+    embedding::cosine_similarity,
+    error::{Result, UmapError},
+    quantize::{Quantization, QuantizedQuery, extend_f32},
+    scoring::CorpusMatrix,
+    types::ChunkRecord,
+};
 
 #[derive(Debug, Clone)]
 pub struct ScoredChunk {
@@ -9,19 +17,170 @@ pub struct ScoredChunk {
 }
 
 pub fn top_k_by_cosine(db: &Db, query_vec: &[f32], k: usize) -> Result<Vec<ScoredChunk>> {
-    let mut all = db.all_chunks()?;
-    let mut scored: Vec<ScoredChunk> = all
-        .drain(..)
-        .map(|rec| {
-            let score = cosine_similarity(&rec.vector, query_vec);
-            ScoredChunk { record: rec, score }
+    top_k_rescored(db, query_vec, k, 0)
+}
+
+/// Like [`top_k_by_cosine`], scoring quantized collections on their int8 or
+/// binary codes. With `rescore > 0` the best `max(k, rescore)` candidates are
+/// re-ranked by exact cosine against their full-precision vectors, which the
+/// collection must keep (see [`VectorStorage`](crate::VectorStorage)).
+pub fn top_k_rescored(
+    db: &Db,
+    query_vec: &[f32],
+    k: usize,
+    rescore: usize,
+) -> Result<Vec<ScoredChunk>> {
+    if let Some(info) = db.embedder_info()?
+        && query_vec.len() != info.dim
+    {
+        return Err(UmapError::DimensionMismatch {
+            expected: info.dim,
+            actual: query_vec.len(),
+        });
+    }
+    let storage = db.vector_storage()?;
+    // Unquantized scores are already exact
+    let rescoring = rescore > 0 && storage.quantization != Quantization::None;
+    if rescoring && !storage.keep_full_precision {
+        return Err(UmapError::invalid(
+            "rescoring needs a collection ingested with full-precision vectors kept",
+        ));
+    }
+
+    let mut scored = match QuantizedQuery::new(storage.quantization, query_vec) {
+        Some(query) => score_quantized(db, &query, query_vec.len())?,
+        None => score_exact(db, query_vec)?,
+    };
+    let candidates = if rescoring { rescore.max(k) } else { k };
+    keep_best(&mut scored, candidates);
+
+    let ids: Vec<i64> = scored.iter().map(|(id, _)| *id).collect();
+    let estimates: HashMap<i64, f32> = scored.into_iter().collect();
+    let mut out: Vec<ScoredChunk> = db
+        .chunks_by_ids(&ids)?
+        .into_iter()
+        .map(|record| {
+            let score = if rescoring {
+                cosine_similarity(&record.vector, query_vec)
+            } else {
+                estimates[&record.id]
+            };
+            ScoredChunk { record, score }
         })
         .collect();
-    scored.sort_by(|a, b| {
-        b.score
-            .partial_cmp(&a.score)
-            .unwrap_or(std::cmp::Ordering::Equal)
-    });
-    scored.truncate(k);
+    out.sort_by(|a, b| by_score(a.score, a.record.id, b.score, b.record.id));
+    out.truncate(k);
+    Ok(out)
+}
+
+/// Exact cosine of every stored f32 vector, read into one contiguous
+/// [`CorpusMatrix`] with the norms saved at insert so each row costs a single
+/// dot product.
+fn score_exact(db: &Db, query_vec: &[f32]) -> Result<Vec<(i64, f32)>> {
+    let rows = db.count_chunks()?.max(0) as usize;
+    let mut ids = Vec::with_capacity(rows);
+    let mut matrix = CorpusMatrix::with_capacity(query_vec.len(), rows);
+    let mut row = Vec::with_capacity(query_vec.len());
+    db.for_each_vector_with_norm(|id, dim, blob, stored| {
+        if dim != query_vec.len() {
+            return Err(UmapError::DimensionMismatch {
+                expected: dim,
+                actual: query_vec.len(),
+            });
+        }
+        row.clear();
+        extend_f32(&mut row, blob);
+        match stored {
+            Some(row_norm) => matrix.push_with_norm(&row, row_norm)?,
+            None => matrix.push(&row)?,
+        }
+        ids.push(id);
+        Ok(())
+    })?;
+    Ok(ids
+        .into_iter()
+        .zip(matrix.cosine_scores(query_vec)?)
+        .collect())
+}
+
+/// Estimated cosine of every stored int8 or binary code.
+fn score_quantized(db: &Db, query: &QuantizedQuery, dim: usize) -> Result<Vec<(i64, f32)>> {
+    let mut scored = Vec::new();
+    db.for_each_vector(|id, d, blob| {
+        if d != dim {
+            return Err(UmapError::DimensionMismatch {
+                expected: d,
+                actual: dim,
+            });
+        }
+        scored.push((id, query.score(blob)));
+        Ok(())
+    })?;
     Ok(scored)
 }
+
+/// Highest score first; ties go to the earlier chunk.
+fn by_score(a_score: f32, a_id: i64, b_score: f32, b_id: i64) -> Ordering {
+    b_score
+        .partial_cmp(&a_score)
+        .unwrap_or(Ordering::Equal)
+        .then(a_id.cmp(&b_id))
+}
+
+/// Reduce `scored` to its `n` best entries, in order, without sorting the rest.
+fn keep_best(scored: &mut Vec<(i64, f32)>, n: usize) {
+    let cmp = |a: &(i64, f32), b: &(i64, f32)| by_score(a.1, a.0, b.1, b.0);
+    if n == 0 {
+        scored.clear();
+        return;
+    }
+    if scored.len() > n {
+        scored.select_nth_unstable_by(n - 1, cmp);
+        scored.truncate(n);
+    }
+    scored.sort_by(cmp);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::chunk::Chunk;
+    use crate::types::SourceSpan;
+
+    #[test]
+    fn exact_scores_match_cosine_similarity() {
+        let db = Db::open(":memory:").unwrap();
+        let chunk = Chunk {
+            text: String::new(),
+            span: SourceSpan {
+                start: 0,
+                end: 0,
+                start_line: 1,
+                end_line: 1,
+            },
+            section: None,
+            symbol: None,
+        };
+        let vectors: Vec<Vec<f32>> = (0..20)
+            .map(|i| {
+                (0..11)
+                    .map(|j| ((i * 7 + j * 3) % 13) as f32 - 6.0)
+                    .collect()
+            })
+            .collect();
+        for (i, v) in vectors.iter().enumerate() {
+            db.insert_chunk("doc", i as i64, &chunk, v).unwrap();
+        }
+        let query: Vec<f32> = (0..11).map(|j| j as f32 - 5.0).collect();
+        let got = top_k_by_cosine(&db, &query, 5).unwrap();
+        let mut expected: Vec<f32> = vectors
+            .iter()
+            .map(|v| cosine_similarity(v, &query))
+            .collect();
+        expected.sort_by(|a, b| b.total_cmp(a));
+        assert_eq!(got.len(), 5);
+        for (g, e) in got.iter().zip(&expected) {
+            assert!((g.score - e).abs() < 1e-5, "{} vs {e}", g.score);
+        }
+    }
+}
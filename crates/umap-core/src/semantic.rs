@@ -0,0 +1,192 @@
+use std::ops::Range;
+
+use crate::chunk::{Chunk, Chunker, LineIndex, token_windows};
+use crate::embedding::{Embedder, cosine_similarity};
+use crate::error::Result;
+use crate::sentence::sentence_spans;
+use crate::tokenizer::Tokenizer;
+
+/// Splits where the topic drifts. Every sentence is embedded with the
+/// collection's embedder; at each sentence boundary the `window` sentences
+/// before and after are compared, and boundaries whose similarity falls in
+/// the lowest `percentile` percent of the document become split points.
+/// A chunk reaches `min_tokens` words before it may end at such a point and
+/// never grows past `tokens_per_chunk`; longer sentences are cut into windows.
+///
+/// Embedders fitted to the corpus (tfidf, lsa) compare sentences with the
+/// state the collection already has. Before the first ingest they have none,
+/// so the first document of a new collection is split by size alone.
+pub struct SemanticChunker<'a> {
+    embedder: &'a dyn Embedder,
+    tokenizer: Tokenizer,
+    window: usize,
+    percentile: usize,
+    min_tokens: usize,
+    tokens_per_chunk: usize,
+}
+
+impl<'a> SemanticChunker<'a> {
+    pub fn new(
+        embedder: &'a dyn Embedder,
+        window: usize,
+        percentile: usize,
+        min_tokens: usize,
+        tokens_per_chunk: usize,
+    ) -> Self {
+        Self {
+            tokenizer: Tokenizer::new(embedder.config().tokenizer),
+            embedder,
+            window,
+            percentile,
+            min_tokens,
+            tokens_per_chunk,
+        }
+    }
+
+    fn words(&self, input: &str, range: &Range<usize>) -> usize {
+        self.tokenizer.word_spans(&input[range.clone()]).len()
+    }
+
+    /// Sentences of `input` with their word counts; sentences over the budget
+    /// are replaced by consecutive windows of it.
+    fn sentences(&self, input: &str) -> Vec<(Range<usize>, usize)> {
+        let mut out = Vec::new();
+        for sentence in sentence_spans(input) {
+            let n = self.words(input, &sentence);
+            if n <= self.tokens_per_chunk {
+                out.push((sentence, n));
+                continue;
+            }
+            for piece in token_windows(&self.tokenizer, input, sentence, self.tokens_per_chunk, 0) {
+                let n = self.words(input, &piece);
+                out.push((piece, n));
+            }
+        }
+        out
+    }
+
+    /// Similarity across each boundary, entry `i` being the one before
+    /// sentence `i + 1`.
+    fn drift(&self, vectors: &[Vec<f32>]) -> Vec<f32> {
+        (1..vectors.len())
+            .map(|i| {
+                let before = sum(&vectors[i.saturating_sub(self.window)..i]);
+                let after = sum(&vectors[i..(i + self.window).min(vectors.len())]);
+                cosine_similarity(&before, &after)
+            })
+            .collect()
+    }
+}
+
+/// Element-wise sum; cosine ignores scale, so it stands in for the mean.
+fn sum(vectors: &[Vec<f32>]) -> Vec<f32> {
+    let mut out = vec![0.0; vectors[0].len()];
+    for v in vectors {
+        for (o, x) in out.iter_mut().zip(v) {
+            *o += x;
+        }
+    }
+    out
+}
+
+/// The `p`th percentile of `values` by nearest rank.
+fn percentile(values: &[f32], p: usize) -> f32 {
+    let mut sorted = values.to_vec();
+    sorted.sort_by(f32::total_cmp);
+    let rank = (p.min(100) * sorted.len()).div_ceil(100).max(1);
+    sorted[rank - 1]
+}
+
+impl Chunker for SemanticChunker<'_> {
+    fn name(&self) -> &str {
+        "semantic"
+    }
+
+    fn params(&self) -> serde_json::Value {
+        serde_json::json!({
+            "tokens_per_chunk": self.tokens_per_chunk,
+            "min_tokens": self.min_tokens,
+            "window": self.window,
+            "percentile": self.percentile,
+            "embedder": self.embedder.name(),
+            "tokenizer": self.tokenizer.config().describe(),
+        })
+    }
+
+    fn chunk(&self, input: &str) -> Result<Vec<Chunk>> {
+        let sentences = self.sentences(input);
+        if sentences.is_empty() {
+            return Ok(Vec::new());
+        }
+        let similarities = if self.embedder.fitted() {
+            let texts: Vec<&str> = sentences.iter().map(|(r, _)| &input[r.clone()]).collect();
+            self.drift(&self.embedder.embed_batch(&texts)?)
+        } else {
+            Vec::new()
+        };
+        // Boundaries near either end compare fewer sentences, which alone
+        // makes them score low, so only full windows set the threshold
+        let interior = similarities
+            .get(self.window - 1..sentences.len().saturating_sub(self.window))
+            .filter(|s| !s.is_empty())
+            .unwrap_or(&similarities);
+        let threshold = (self.percentile > 0 && !interior.is_empty())
+            .then(|| percentile(interior, self.percentile));
+
+        let mut ranges: Vec<(Range<usize>, usize)> = Vec::new();
+        let mut first = 0usize;
+        let mut total = sentences[0].1;
+        for (i, (_, n)) in sentences.iter().enumerate().skip(1) {
+            let drifted = threshold.is_some_and(|t| similarities[i - 1] <= t);
+            if (drifted && total >= self.min_tokens) || total + n > self.tokens_per_chunk {
+                ranges.push((sentences[first].0.start..sentences[i - 1].0.end, total));
+                first = i;
+                total = 0;
+            }
+            total += n;
+        }
+        ranges.push((
+            sentences[first].0.start..sentences[sentences.len() - 1].0.end,
+            total,
+        ));
+
+        // A short tail joins the chunk before it when both fit the budget
+        if let [.., (prev, m), (last, n)] = ranges.as_slice()
+            && *n < self.min_tokens
+            && m + n <= self.tokens_per_chunk
+        {
+            let merged = (prev.start..last.end, m + n);
+            ranges.truncate(ranges.len() - 2);
+            ranges.push(merged);
+        }
+
+        let lines = LineIndex::new(input);
+        Ok(ranges
+            .into_iter()
+            .map(|(r, _)| lines.chunk(input, r.start, r.end))
+            .collect())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::embedding::Vocabulary;
+    use crate::lsa::LsaEmbedder;
+
+    #[test]
+    fn unfitted_embedders_split_by_size() {
+        let lsa = LsaEmbedder::new(4, 64, false, Vocabulary::Learned);
+        assert!(!lsa.fitted());
+        let chunker = SemanticChunker::new(&lsa, 2, 50, 4, 12);
+        let text = "The cat sat on the mat. ".repeat(10);
+        let chunks = chunker.chunk(&text).unwrap();
+        assert_eq!(chunks.len(), 5);
+        for chunk in &chunks {
+            assert_eq!(
+                chunker.words(&text, &(chunk.span.start..chunk.span.end)),
+                12
+            );
+        }
+    }
+}
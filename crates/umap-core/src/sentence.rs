@@ -0,0 +1,205 @@
+use std::ops::Range;
+use std::sync::LazyLock;
+
+use regex::Regex;
+
+use crate::chunk::trimmed;
+
+/// Places a sentence may end: runs of terminal punctuation (ASCII, ellipsis,
+/// full-width with any closing brackets) and blank lines.
+static CANDIDATE: LazyLock<Regex> =
+    LazyLock::new(|| Regex::new(r"[.!?…]+|[。！？｡]+[」』”）〕】]*|\n[ \t\r]*\n").unwrap());
+
+/// Abbreviations that do not end a sentence, lowercase and without the final
+/// period.
+const ABBREVIATIONS: &[&str] = &[
+    "mr", "mrs", "ms", "mx", "dr", "prof", "rev", "hon", "st", "mt", "ft", "gen", "col", "capt",
+    "lt", "sgt", "cpl", "gov", "sen", "rep", "pres", "messrs", "mme", "mlle", "e.g", "i.e", "cf",
+    "viz", "vs", "al", "approx", "ca", "dept", "est", "jan", "feb", "mar", "apr", "jun", "jul",
+    "aug", "sep", "sept", "oct", "nov", "dec",
+];
+
+/// Abbreviations that only continue the sentence before a number ("No. 5",
+/// "Fig. 3"), since most are ordinary words too.
+const NUMBERED: &[&str] = &[
+    "no", "nos", "p", "pp", "vol", "vols", "ch", "sec", "eq", "eqs", "fig", "figs",
+];
+
+/// Closing quotes and brackets that belong to the sentence before them.
+const CLOSERS: &[char] = &['"', '\'', '”', '’', ')', ']', '}', '»'];
+
+/// Opening quotes and brackets skipped when looking at the next word.
+const OPENERS: &[char] = &['"', '\'', '“', '‘', '(', '[', '{', '«', '¿', '¡'];
+
+/// Byte ranges of the sentences in `input`, trimmed.
+///
+/// A rule-based detector: a run of `.`, `!`, `?` or `…` ends a sentence when
+/// it is followed by whitespace (so "3.14" and "example.com" stay whole) and
+/// the next word does not start in lowercase. A single period also does not
+/// end one after a known abbreviation ("Mr.", "e.g."), a numbering
+/// abbreviation before a number ("No. 5"), an initial ("J. R. R. Tolkien";
+/// "I" only beside another initial, since it is usually the pronoun),
+/// or a list number at the start of a line ("2. Item"). Closing quotes and
+/// brackets stay with the sentence they close. Full-width terminators
+/// (Chinese/Japanese) need no following space, and blank lines always end a
+/// sentence.
+pub fn sentence_spans(input: &str) -> Vec<Range<usize>> {
+    let mut out = Vec::new();
+    let mut start = 0usize;
+    for m in CANDIDATE.find_iter(input) {
+        if let Some(end) = boundary(input, m.range()) {
+            out.extend(trimmed(input, start..end));
+            start = end;
+        }
+    }
+    out.extend(trimmed(input, start..input.len()));
+    out
+}
+
+/// Where the sentence ends if the terminator at `run` ends one.
+fn boundary(input: &str, run: Range<usize>) -> Option<usize> {
+    let punct = &input[run.clone()];
+    if !punct.starts_with(['.', '!', '?', '…']) {
+        // Blank line or full-width terminator
+        return Some(run.end);
+    }
+    let end = run.end
+        + input[run.end..]
+            .chars()
+            .take_while(|c| CLOSERS.contains(c))
+            .map(char::len_utf8)
+            .sum::<usize>();
+    let rest = &input[end..];
+    if !rest.is_empty() && !rest.starts_with(char::is_whitespace) {
+        return None;
+    }
+    let after = rest.trim_start().trim_start_matches(OPENERS);
+    if after.starts_with(char::is_lowercase) {
+        return None;
+    }
+    if punct == "." && abbreviated(input, run.start, after) {
+        return None;
+    }
+    Some(end)
+}
+
+/// Whether `token` is an initial with its period, like the "R." in "J. R. R."
+fn is_initial(token: &str) -> bool {
+    let mut chars = token.chars();
+    matches!(
+        (chars.next(), chars.next(), chars.next()),
+        (Some(c), Some('.'), None) if c.is_uppercase()
+    )
+}
+
+/// Whether the period at `dot`, followed by `after`, closes an abbreviation
+/// rather than a sentence.
+fn abbreviated(input: &str, dot: usize, after: &str) -> bool {
+    let before = &input[..dot];
+    let token = before.rsplit(char::is_whitespace).next().unwrap_or("");
+    let word = token.trim_start_matches(OPENERS);
+    let lower = word.to_lowercase();
+    if ABBREVIATIONS.contains(&lower.as_str()) {
+        return true;
+    }
+    if NUMBERED.contains(&lower.as_str()) && after.starts_with(|c: char| c.is_ascii_digit()) {
+        return true;
+    }
+    let mut chars = word.chars();
+    if let (Some(c), None) = (chars.next(), chars.next())
+        && c.is_uppercase()
+    {
+        // "I" is usually the pronoun ("It was I. Then..."), and an initial
+        // only next to another one ("I. M. Pei", "J. I. Smith")
+        if c != 'I' {
+            return true;
+        }
+        let previous = before[..dot - token.len()].split_whitespace().next_back();
+        let next = after.split_whitespace().next();
+        return previous.is_some_and(is_initial) || next.is_some_and(is_initial);
+    }
+    // "1." or "12." alone at the start of a line is a list marker
+    !word.is_empty()
+        && word.chars().all(|c| c.is_ascii_digit())
+        && before[..dot - token.len()]
+            .trim_end_matches([' ', '\t'])
+            .chars()
+            .next_back()
+            .is_none_or(|c| c == '\n')
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn sentences(input: &str) -> Vec<&str> {
+        sentence_spans(input)
+            .into_iter()
+            .map(|r| &input[r])
+            .collect()
+    }
+
+    #[test]
+    fn splits_like_a_reader() {
+        let cases: &[(&str, &[&str])] = &[
+            (
+                "Mr. Smith met Dr. Jones. They talked.",
+                &["Mr. Smith met Dr. Jones.", "They talked."],
+            ),
+            (
+                "Bring tools, e.g. Hammers and saws. Then stop, i.e. Rest.",
+                &[
+                    "Bring tools, e.g. Hammers and saws.",
+                    "Then stop, i.e. Rest.",
+                ],
+            ),
+            (
+                "Pi is about 3.14 here. See example.com for more. Done.",
+                &[
+                    "Pi is about 3.14 here.",
+                    "See example.com for more.",
+                    "Done.",
+                ],
+            ),
+            (
+                "Wait... Then it happened.",
+                &["Wait...", "Then it happened."],
+            ),
+            (
+                "She said \"Stop.\" Then he left.",
+                &["She said \"Stop.\"", "Then he left."],
+            ),
+            (
+                "(Really?) Yes. [Sure.] Fine!",
+                &["(Really?)", "Yes.", "[Sure.]", "Fine!"],
+            ),
+            ("\"Is it?\" she asked.", &["\"Is it?\" she asked."]),
+            (
+                "J. R. R. Tolkien wrote it. He died in 1973.",
+                &["J. R. R. Tolkien wrote it.", "He died in 1973."],
+            ),
+            ("I. M. Pei designed it.", &["I. M. Pei designed it."]),
+            ("It was I. Then it ended.", &["It was I.", "Then it ended."]),
+            (
+                "See No. 5 on the list. Say no. Then go.",
+                &["See No. 5 on the list.", "Say no.", "Then go."],
+            ),
+            (
+                "Steps:\n1. Open the lid.\n2. Pour it in.\n10. Wait.",
+                &["Steps:\n1. Open the lid.", "2. Pour it in.", "10. Wait."],
+            ),
+            (
+                "「こんにちは。」彼は言った。次だ！本当？",
+                &["「こんにちは。」", "彼は言った。", "次だ！", "本当？"],
+            ),
+            (
+                "A title\n\nBody text here.",
+                &["A title", "Body text here."],
+            ),
+            ("", &[]),
+        ];
+        for (input, expected) in cases {
+            assert_eq!(&sentences(input), expected, "{input:?}");
+        }
+    }
+}
@@ -0,0 +1,206 @@
+use std::collections::{HashMap, HashSet};
+
+use crate::db::Db;
+use crate::embedding::{Embedder, EmbedderConfig, Vocabulary, fxhash, l2_normalize, signed_hash};
+use crate::error::{Result, UmapError};
+use crate::tokenizer::{Tokenizer, TokenizerConfig};
+use crate::types::TermStat;
+
+const DOC_COUNT_KEY: &str = "tfidf_doc_count";
+
+/// TF-IDF weighted bag of words. Document frequencies are accumulated over
+/// every ingested chunk and persisted in the collection, so a query is
+/// weighted against the same corpus statistics as the chunks it is compared to.
+///
+/// Vectors already stored are not re-weighted when later ingests shift the
+/// statistics; for a stable corpus the drift is small.
+pub struct TfidfEmbedder {
+    dim: usize,
+    sublinear_tf: bool,
+    vocabulary: Vocabulary,
+    tokenizer: Tokenizer,
+    doc_count: u64,
+    df: HashMap<String, u64>,
+    // Learned mode only: term -> component, filled in order of document frequency
+    vocab: HashMap<String, usize>,
+}
+
+impl TfidfEmbedder {
+    pub fn new(dim: usize, sublinear_tf: bool, vocabulary: Vocabulary) -> Self {
+        Self {
+            dim,
+            sublinear_tf,
+            vocabulary,
+            tokenizer: Tokenizer::default(),
+            doc_count: 0,
+            df: HashMap::new(),
+            vocab: HashMap::new(),
+        }
+    }
+
+    pub fn with_tokenizer(mut self, config: TokenizerConfig) -> Self {
+        self.tokenizer = Tokenizer::new(config);
+        self
+    }
+
+    pub fn doc_count(&self) -> u64 {
+        self.doc_count
+    }
+
+    /// Smoothed inverse document frequency, `ln((1 + N) / (1 + df)) + 1`.
+    pub fn idf(&self, term: &str) -> f32 {
+        let df = self.df.get(term).copied().unwrap_or(0);
+        (((1 + self.doc_count) as f32) / ((1 + df) as f32)).ln() + 1.0
+    }
+
+    /// Un-normalized TF-IDF vector of length `dim`.
+    pub(crate) fn weighted(&self, text: &str) -> Vec<f32> {
+        let mut tf: HashMap<String, u32> = HashMap::new();
+        for t in self.tokenizer.tokenize(text) {
+            *tf.entry(t).or_insert(0) += 1;
+        }
+        let mut vec = vec![0f32; self.dim];
+        for (term, count) in tf {
+            let tf_w = if self.sublinear_tf {
+                1.0 + (count as f32).ln()
+            } else {
+                count as f32
+            };
+            let w = tf_w * self.idf(&term);
+            match self.vocabulary {
+                Vocabulary::Hashing => {
+                    let (idx, sign) = signed_hash(fxhash(&term), self.dim);
+                    vec[idx] += sign * w;
+                }
+                Vocabulary::Learned => {
+                    // Out-of-vocabulary terms have no component to land in
+                    if let Some(&idx) = self.vocab.get(&term) {
+                        vec[idx] += w;
+                    }
+                }
+            }
+        }
+        vec
+    }
+
+    /// Assign free components to the most frequent terms not yet in the
+    /// vocabulary. Existing assignments never move, so stored vectors stay valid.
+    fn grow_vocab(&mut self) {
+        if self.vocabulary != Vocabulary::Learned || self.vocab.len() >= self.dim {
+            return;
+        }
+        let mut candidates: Vec<(&String, u64)> = self
+            .df
+            .iter()
+            .filter(|(t, _)| !self.vocab.contains_key(*t))
+            .map(|(t, df)| (t, *df))
+            .collect();
+        // Highest df first; ties broken alphabetically for determinism
+        candidates.sort_by(|a, b| b.1.cmp(&a.1).then_with(|| a.0.cmp(b.0)));
+        let free = self.dim - self.vocab.len();
+        let picked: Vec<String> = candidates
+            .into_iter()
+            .take(free)
+            .map(|(t, _)| t.clone())
+            .collect();
+        for term in picked {
+            let idx = self.vocab.len();
+            self.vocab.insert(term, idx);
+        }
+    }
+}
+
+impl Embedder for TfidfEmbedder {
+    fn name(&self) -> &str {
+        "tfidf"
+    }
+
+    fn dim(&self) -> usize {
+        self.dim
+    }
+
+    // 2: hash signs come from the top bit rather than the bucket's low bit
+    fn version(&self) -> u32 {
+        2
+    }
+
+    fn params(&self) -> serde_json::Value {
+        serde_json::json!({
+            "sublinear_tf": self.sublinear_tf,
+            "vocabulary": self.vocabulary,
+            "idf": "smooth",
+            "hash": "fnv1a-64",
+            "tokenizer": self.tokenizer.config().describe(),
+        })
+    }
+
+    fn config(&self) -> EmbedderConfig {
+        EmbedderConfig {
+            name: "tfidf".to_string(),
+            dim: self.dim,
+            sublinear_tf: self.sublinear_tf,
+            vocabulary: self.vocabulary,
+            tokenizer: self.tokenizer.config().clone(),
+            ..EmbedderConfig::default()
+        }
+    }
+
+    fn cacheable(&self) -> bool {
+        false
+    }
+
+    fn fitted(&self) -> bool {
+        self.doc_count > 0
+    }
+
+    fn fit(&mut self, texts: &[&str]) -> Result<()> {
+        for text in texts {
+            let unique: HashSet<String> = self.tokenizer.tokenize(text).into_iter().collect();
+            for term in unique {
+                *self.df.entry(term).or_insert(0) += 1;
+            }
+        }
+        self.doc_count += texts.len() as u64;
+        self.grow_vocab();
+        Ok(())
+    }
+
+    fn load_state(&mut self, db: &Db) -> Result<()> {
+        self.doc_count = match db.meta(DOC_COUNT_KEY)? {
+            Some(v) => v
+                .parse()
+                .map_err(|_| UmapError::corrupt(format!("bad {DOC_COUNT_KEY} value: {v}")))?,
+            None => 0,
+        };
+        self.df.clear();
+        self.vocab.clear();
+        for st in db.term_stats()? {
+            if let Some(idx) = st.index {
+                self.vocab.insert(st.term.clone(), idx);
+            }
+            self.df.insert(st.term, st.df);
+        }
+        Ok(())
+    }
+
+    fn save_state(&self, db: &Db) -> Result<()> {
+        let stats: Vec<TermStat> = self
+            .df
+            .iter()
+            .map(|(term, df)| TermStat {
+                term: term.clone(),
+                df: *df,
+                index: self.vocab.get(term).copied(),
+            })
+            .collect();
+        db.save_term_stats(&stats)?;
+        db.set_meta(DOC_COUNT_KEY, &self.doc_count.to_string())
+    }
+
+    fn embed_batch(&self, texts: &[&str]) -> Result<Vec<Vec<f32>>> {
+        Ok(texts
+            .iter()
+            .map(|t| l2_normalize(self.weighted(t)))
+            .collect())
+    }
+}
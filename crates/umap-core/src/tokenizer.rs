@@ -0,0 +1,402 @@
+use std::collections::HashSet;
+use std::ops::Range;
+use std::str::FromStr;
+
+use rust_stemmers::{Algorithm, Stemmer};
+use serde::{Deserialize, Serialize};
+use unicode_normalization::UnicodeNormalization;
+use unicode_segmentation::UnicodeSegmentation;
+
+use crate::error::{Result, UmapError};
+
+/// Languages with both a stopword list and a Snowball stemmer.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Language {
+    Arabic,
+    Danish,
+    Dutch,
+    English,
+    Finnish,
+    French,
+    German,
+    Greek,
+    Hungarian,
+    Italian,
+    Norwegian,
+    Portuguese,
+    Romanian,
+    Russian,
+    Spanish,
+    Swedish,
+    Turkish,
+}
+
+impl Language {
+    const ALL: &[(Language, &str, &str)] = &[
+        (Language::Arabic, "arabic", "ar"),
+        (Language::Danish, "danish", "da"),
+        (Language::Dutch, "dutch", "nl"),
+        (Language::English, "english", "en"),
+        (Language::Finnish, "finnish", "fi"),
+        (Language::French, "french", "fr"),
+        (Language::German, "german", "de"),
+        (Language::Greek, "greek", "el"),
+        (Language::Hungarian, "hungarian", "hu"),
+        (Language::Italian, "italian", "it"),
+        (Language::Norwegian, "norwegian", "no"),
+        (Language::Portuguese, "portuguese", "pt"),
+        (Language::Romanian, "romanian", "ro"),
+        (Language::Russian, "russian", "ru"),
+        (Language::Spanish, "spanish", "es"),
+        (Language::Swedish, "swedish", "sv"),
+        (Language::Turkish, "turkish", "tr"),
+    ];
+
+    /// ISO 639-1 code.
+    pub fn code(self) -> &'static str {
+        Self::ALL
+            .iter()
+            .find(|(l, _, _)| *l == self)
+            .map(|(_, _, code)| *code)
+            .unwrap_or("en")
+    }
+
+    fn algorithm(self) -> Algorithm {
+        match self {
+            Language::Arabic => Algorithm::Arabic,
+            Language::Danish => Algorithm::Danish,
+            Language::Dutch => Algorithm::Dutch,
+            Language::English => Algorithm::English,
+            Language::Finnish => Algorithm::Finnish,
+            Language::French => Algorithm::French,
+            Language::German => Algorithm::German,
+            Language::Greek => Algorithm::Greek,
+            Language::Hungarian => Algorithm::Hungarian,
+            Language::Italian => Algorithm::Italian,
+            Language::Norwegian => Algorithm::Norwegian,
+            Language::Portuguese => Algorithm::Portuguese,
+            Language::Romanian => Algorithm::Romanian,
+            Language::Russian => Algorithm::Russian,
+            Language::Spanish => Algorithm::Spanish,
+            Language::Swedish => Algorithm::Swedish,
+            Language::Turkish => Algorithm::Turkish,
+        }
+    }
+}
+
+impl FromStr for Language {
+    type Err = UmapError;
+
+    /// Accepts English names ("german") or ISO 639-1 codes ("de").
+    fn from_str(s: &str) -> Result<Self> {
+        let s = s.to_lowercase();
+        Self::ALL
+            .iter()
+            .find(|(_, name, code)| *name == s || *code == s)
+            .map(|(l, _, _)| *l)
+            .ok_or_else(|| UmapError::invalid(format!("unsupported language: {s}")))
+    }
+}
+
+/// How text is split into words.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Segmentation {
+    /// Split on anything that is not alphanumeric or an apostrophe
+    #[default]
+    Simple,
+    /// Unicode word boundaries (UAX #29); runs of Chinese/Japanese characters,
+    /// which carry no spaces, are embedded as overlapping character bigrams
+    Unicode,
+}
+
+impl FromStr for Segmentation {
+    type Err = UmapError;
+
+    fn from_str(s: &str) -> Result<Self> {
+        match s.to_lowercase().as_str() {
+            "simple" => Ok(Segmentation::Simple),
+            "unicode" | "uax29" => Ok(Segmentation::Unicode),
+            other => Err(UmapError::invalid(format!(
+                "unknown segmentation: {other} (expected simple or unicode)"
+            ))),
+        }
+    }
+}
+
+/// Optional steps layered on top of the simple lowercase/alphanumeric split.
+/// The default enables none of them, so it tokenizes exactly like [`tokenize`].
+///
+/// [`tokenize`]: crate::tokenize
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct TokenizerConfig {
+    /// Word splitting rule
+    #[serde(default)]
+    pub segmentation: Segmentation,
+    /// Apply Unicode NFKC normalization before lowercasing, so full-width,
+    /// ligature and composed/decomposed forms split and match consistently
+    #[serde(default)]
+    pub nfkc: bool,
+    /// Drop the stopwords of this language
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub stopwords: Option<Language>,
+    /// Reduce words to their Snowball stem in this language
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub stem: Option<Language>,
+    /// Also emit character n-grams of each word with lengths in `min..=max`
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub char_ngrams: Option<(usize, usize)>,
+}
+
+impl TokenizerConfig {
+    pub fn is_simple(&self) -> bool {
+        *self == Self::default()
+    }
+
+    /// Identity for embedder params: `"simple"` for the default (which keeps
+    /// collections built before these options compatible), else the settings.
+    pub fn describe(&self) -> serde_json::Value {
+        if self.is_simple() {
+            serde_json::Value::from("simple")
+        } else {
+            serde_json::to_value(self).unwrap_or(serde_json::Value::Null)
+        }
+    }
+
+    /// Parse an n-gram range such as `"3-5"` (or `"3"` for a single length).
+    pub fn parse_ngram_range(spec: &str) -> Result<(usize, usize)> {
+        let bad =
+            || UmapError::invalid(format!("invalid n-gram range: {spec} (expected e.g. 3-5)"));
+        let (lo, hi) = match spec.split_once('-') {
+            Some((lo, hi)) => (lo.trim(), hi.trim()),
+            None => (spec.trim(), spec.trim()),
+        };
+        let (lo, hi) = (
+            lo.parse::<usize>().map_err(|_| bad())?,
+            hi.parse::<usize>().map_err(|_| bad())?,
+        );
+        if lo == 0 || lo > hi {
+            return Err(bad());
+        }
+        Ok((lo, hi))
+    }
+}
+
+/// A [`TokenizerConfig`] made ready to run: stopword set and stemmer built once.
+pub struct Tokenizer {
+    config: TokenizerConfig,
+    stopwords: HashSet<String>,
+    stemmer: Option<Stemmer>,
+}
+
+impl Default for Tokenizer {
+    fn default() -> Self {
+        Self::new(TokenizerConfig::default())
+    }
+}
+
+impl Tokenizer {
+    pub fn new(config: TokenizerConfig) -> Self {
+        let stopwords = match config.stopwords {
+            Some(lang) => stop_words::get(lang.code())
+                .iter()
+                .map(|w| w.to_lowercase())
+                .collect(),
+            None => HashSet::new(),
+        };
+        let stemmer = config.stem.map(|lang| Stemmer::create(lang.algorithm()));
+        Self {
+            config,
+            stopwords,
+            stemmer,
+        }
+    }
+
+    pub fn config(&self) -> &TokenizerConfig {
+        &self.config
+    }
+
+    /// Normalized words before stopword removal, stemming and n-grams. Token
+    /// based chunking counts these so chunk text stays readable. With Unicode
+    /// segmentation each Chinese/Japanese character is its own word.
+    pub fn words(&self, text: &str) -> Vec<String> {
+        self.segments(text).into_iter().map(|s| s.text).collect()
+    }
+
+    /// Byte ranges in `text` of the words [`Tokenizer::words`] yields, found
+    /// by the same rules on the unnormalized text so chunks can be cut from
+    /// the original. Counts only differ for rare compatibility characters
+    /// that NFKC expands into separators.
+    pub fn word_spans(&self, text: &str) -> Vec<Range<usize>> {
+        match self.config.segmentation {
+            Segmentation::Simple => {
+                let mut out = Vec::new();
+                let mut start = None;
+                for (i, c) in text.char_indices().chain([(text.len(), ' ')]) {
+                    let in_word = i < text.len() && (c.is_alphanumeric() || c == '\'');
+                    match (in_word, start) {
+                        (true, None) => start = Some(i),
+                        (false, Some(s)) => {
+                            start = None;
+                            let word = &text[s..i];
+                            let trimmed = word.trim_matches('\'');
+                            if trimmed.is_empty() {
+                                continue;
+                            }
+                            let lead = word.len() - word.trim_start_matches('\'').len();
+                            if self.normalize(trimmed).len() > 1 {
+                                out.push(s + lead..s + lead + trimmed.len());
+                            }
+                        }
+                        _ => {}
+                    }
+                }
+                out
+            }
+            Segmentation::Unicode => {
+                let mut out = Vec::new();
+                for (start, word) in text.unicode_word_indices() {
+                    if word.chars().any(is_cjk) {
+                        out.extend(
+                            word.char_indices()
+                                .map(|(i, c)| start + i..start + i + c.len_utf8()),
+                        );
+                    } else if word.chars().count() > 1 {
+                        out.push(start..start + word.len());
+                    }
+                }
+                out
+            }
+        }
+    }
+
+    fn normalize(&self, text: &str) -> String {
+        if self.config.nfkc {
+            text.nfkc().collect::<String>().to_lowercase()
+        } else {
+            text.to_lowercase()
+        }
+    }
+
+    fn segments(&self, text: &str) -> Vec<Segment> {
+        let lowered = self.normalize(text);
+        match self.config.segmentation {
+            Segmentation::Simple => lowered
+                .split(|c: char| !c.is_alphanumeric() && c != '\'')
+                .map(|s| s.trim_matches('\''))
+                .filter(|s| !s.is_empty())
+                .filter(|s| s.len() > 1)
+                .map(|s| Segment {
+                    text: s.to_string(),
+                    cjk: false,
+                    joined: false,
+                })
+                .collect(),
+            Segmentation::Unicode => {
+                let mut out: Vec<Segment> = Vec::new();
+                let mut prev_end = None;
+                for (start, word) in lowered.unicode_word_indices() {
+                    let joined = prev_end == Some(start);
+                    prev_end = Some(start + word.len());
+                    // UAX #29 already yields Han and kana one character at a
+                    // time; split mixed words the same way so runs are uniform
+                    if word.chars().any(is_cjk) {
+                        for (i, c) in word.char_indices() {
+                            out.push(Segment {
+                                text: c.to_string(),
+                                cjk: is_cjk(c),
+                                joined: joined || i > 0,
+                            });
+                        }
+                    } else if word.chars().count() > 1 {
+                        out.push(Segment {
+                            text: word.to_string(),
+                            cjk: false,
+                            joined: false,
+                        });
+                    }
+                }
+                out
+            }
+        }
+    }
+
+    /// Tokens for embedding: words with every configured step applied.
+    pub fn tokenize(&self, text: &str) -> Vec<String> {
+        let segments = self.segments(text);
+        let mut out = Vec::new();
+        let mut i = 0;
+        while i < segments.len() {
+            if segments[i].cjk {
+                // A run of adjacent CJK characters becomes its bigrams; a lone
+                // character stands for itself
+                let start = i;
+                i += 1;
+                while i < segments.len() && segments[i].cjk && segments[i].joined {
+                    i += 1;
+                }
+                let run = &segments[start..i];
+                if run.len() == 1 {
+                    out.push(run[0].text.clone());
+                } else {
+                    out.extend(
+                        run.windows(2)
+                            .map(|w| format!("{}{}", w[0].text, w[1].text)),
+                    );
+                }
+                continue;
+            }
+            let word = &segments[i].text;
+            i += 1;
+            if self.stopwords.contains(word) {
+                continue;
+            }
+            let word = match &self.stemmer {
+                Some(s) => s.stem(word).into_owned(),
+                None => word.clone(),
+            };
+            if let Some((lo, hi)) = self.config.char_ngrams {
+                push_char_ngrams(&word, lo, hi, &mut out);
+            }
+            out.push(word);
+        }
+        out
+    }
+}
+
+struct Segment {
+    text: String,
+    /// A single Chinese or Japanese character
+    cjk: bool,
+    /// Directly follows the previous segment with nothing in between
+    joined: bool,
+}
+
+/// Han ideographs and Japanese kana: scripts written without spaces between
+/// words. Hangul is space-separated and segments like alphabetic text.
+fn is_cjk(c: char) -> bool {
+    matches!(c,
+        '\u{3040}'..='\u{30FF}'   // Hiragana, Katakana
+        | '\u{31F0}'..='\u{31FF}' // Katakana phonetic extensions
+        | '\u{3400}'..='\u{4DBF}' // CJK Extension A
+        | '\u{4E00}'..='\u{9FFF}' // CJK Unified Ideographs
+        | '\u{F900}'..='\u{FAFF}' // CJK Compatibility Ideographs
+        | '\u{FF66}'..='\u{FF9F}' // Half-width Katakana
+        | '\u{20000}'..='\u{2FA1F}' // Extensions B-F, compatibility supplement
+    )
+}
+
+/// Character n-grams of `<word>`; the angle brackets mark word boundaries and
+/// can never occur inside a word, so n-grams do not collide with real tokens.
+pub(crate) fn push_char_ngrams(word: &str, lo: usize, hi: usize, out: &mut Vec<String>) {
+    let chars: Vec<char> = format!("<{word}>").chars().collect();
+    for n in lo..=hi {
+        if n > chars.len() {
+            break;
+        }
+        for window in chars.windows(n) {
+            out.push(window.iter().collect());
+        }
+    }
+}
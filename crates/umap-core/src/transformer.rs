@@ -0,0 +1,206 @@
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex, OnceLock};
+
+use candle_core::{DType, Device, Tensor};
+use candle_nn::VarBuilder;
+use candle_transformers::models::bert::{BertModel, Config, DTYPE};
+use tokenizers::{PaddingParams, PaddingStrategy, Tokenizer, TruncationParams};
+
+use crate::embedding::{Embedder, EmbedderConfig, file_fingerprint};
+use crate::error::{Result, UmapError};
+
+/// A BERT-style encoder and its tokenizer, loaded from a Hugging Face style
+/// model directory (`config.json`, `tokenizer.json`, and `model.safetensors`
+/// or `pytorch_model.bin`).
+pub struct TransformerModel {
+    model: BertModel,
+    tokenizer: Tokenizer,
+    config: Config,
+}
+
+impl TransformerModel {
+    pub fn load(dir: &Path) -> Result<Self> {
+        let config_path = dir.join("config.json");
+        let config: Config = serde_json::from_str(&std::fs::read_to_string(&config_path)?)
+            .map_err(|e| {
+                UmapError::invalid(format!("bad model config {}: {e}", config_path.display()))
+            })?;
+
+        let mut tokenizer = Tokenizer::from_file(dir.join("tokenizer.json"))
+            .map_err(|e| UmapError::invalid(format!("bad tokenizer in {}: {e}", dir.display())))?;
+        tokenizer.with_padding(Some(PaddingParams {
+            strategy: PaddingStrategy::BatchLongest,
+            ..PaddingParams::default()
+        }));
+        tokenizer
+            .with_truncation(Some(TruncationParams {
+                max_length: config.max_position_embeddings,
+                ..TruncationParams::default()
+            }))
+            .map_err(|e| UmapError::invalid(format!("bad tokenizer truncation: {e}")))?;
+
+        let device = Device::Cpu;
+        let weights = weights_path(dir);
+        let vb = if weights.ends_with("model.safetensors") {
+            // SAFETY: the weights file is not expected to change while mapped
+            unsafe { VarBuilder::from_mmaped_safetensors(&[weights], DTYPE, &device) }
+        } else {
+            VarBuilder::from_pth(weights, DTYPE, &device)
+        }
+        .map_err(numerical)?;
+        let model = BertModel::load(vb, &config).map_err(numerical)?;
+        Ok(Self {
+            model,
+            tokenizer,
+            config,
+        })
+    }
+
+    pub fn hidden_size(&self) -> usize {
+        self.config.hidden_size
+    }
+
+    /// Mean-pooled last hidden states for one padded batch.
+    fn embed_padded(&self, texts: &[&str], normalize: bool) -> Result<Vec<Vec<f32>>> {
+        let encodings = self
+            .tokenizer
+            .encode_batch(texts.to_vec(), true)
+            .map_err(|e| UmapError::invalid(format!("tokenization failed: {e}")))?;
+        let device = &self.model.device;
+        let ids = encodings
+            .iter()
+            .map(|e| Tensor::new(e.get_ids(), device))
+            .collect::<candle_core::Result<Vec<_>>>()
+            .map_err(numerical)?;
+        let masks = encodings
+            .iter()
+            .map(|e| Tensor::new(e.get_attention_mask(), device))
+            .collect::<candle_core::Result<Vec<_>>>()
+            .map_err(numerical)?;
+
+        let pooled = (|| -> candle_core::Result<Tensor> {
+            let input_ids = Tensor::stack(&ids, 0)?;
+            let attention_mask = Tensor::stack(&masks, 0)?;
+            let token_type_ids = input_ids.zeros_like()?;
+            let hidden = self
+                .model
+                .forward(&input_ids, &token_type_ids, Some(&attention_mask))?;
+            // Average only over real tokens, not padding
+            let mask = attention_mask.to_dtype(DType::F32)?.unsqueeze(2)?;
+            let summed = hidden.broadcast_mul(&mask)?.sum(1)?;
+            let counts = mask.sum(1)?.clamp(1e-9, f32::MAX)?;
+            let mean = summed.broadcast_div(&counts)?;
+            if normalize {
+                let norms = mean.sqr()?.sum_keepdim(1)?.sqrt()?.clamp(1e-12, f32::MAX)?;
+                mean.broadcast_div(&norms)
+            } else {
+                Ok(mean)
+            }
+        })()
+        .map_err(numerical)?;
+        pooled.to_vec2::<f32>().map_err(numerical)
+    }
+}
+
+/// `model.safetensors`, falling back to `pytorch_model.bin`.
+fn weights_path(dir: &Path) -> PathBuf {
+    let safetensors = dir.join("model.safetensors");
+    if safetensors.exists() {
+        safetensors
+    } else {
+        dir.join("pytorch_model.bin")
+    }
+}
+
+/// Fingerprint of the files a model directory is loaded from.
+fn model_fingerprint(dir: &Path) -> Result<String> {
+    let (config, tokenizer) = (dir.join("config.json"), dir.join("tokenizer.json"));
+    file_fingerprint(&[&config, &tokenizer, &weights_path(dir)])
+}
+
+/// Transformer models by directory and fingerprint, shared across embedder
+/// instances so the server loads the weights once rather than per request,
+/// and again only when they are replaced.
+fn cached_model(dir: &Path, fingerprint: &str) -> Result<Arc<TransformerModel>> {
+    type Cache = HashMap<(PathBuf, String), Arc<TransformerModel>>;
+    static CACHE: OnceLock<Mutex<Cache>> = OnceLock::new();
+    let cache = CACHE.get_or_init(|| Mutex::new(HashMap::new()));
+    let mut guard = cache.lock().unwrap_or_else(|e| e.into_inner());
+    let key = (dir.to_path_buf(), fingerprint.to_string());
+    if let Some(m) = guard.get(&key) {
+        return Ok(m.clone());
+    }
+    let loaded = Arc::new(TransformerModel::load(dir)?);
+    guard.retain(|(d, _), _| d != dir);
+    guard.insert(key, loaded.clone());
+    Ok(loaded)
+}
+
+/// Sentence embeddings from a local BERT-style model: mean pooling over the
+/// last hidden layer, optionally L2-normalized. Runs on CPU; texts longer than
+/// the model's position limit are truncated.
+pub struct TransformerEmbedder {
+    path: String,
+    fingerprint: String,
+    normalize: bool,
+    batch_size: usize,
+    model: Arc<TransformerModel>,
+}
+
+impl TransformerEmbedder {
+    pub fn open(path: &str, normalize: bool, batch_size: usize) -> Result<Self> {
+        let fingerprint = model_fingerprint(Path::new(path))?;
+        Ok(Self {
+            path: path.to_string(),
+            model: cached_model(Path::new(path), &fingerprint)?,
+            fingerprint,
+            normalize,
+            batch_size: batch_size.max(1),
+        })
+    }
+}
+
+impl Embedder for TransformerEmbedder {
+    fn name(&self) -> &str {
+        "transformer"
+    }
+
+    fn dim(&self) -> usize {
+        self.model.hidden_size()
+    }
+
+    fn params(&self) -> serde_json::Value {
+        serde_json::json!({
+            "path": self.path,
+            "fingerprint": self.fingerprint,
+            "model_type": self.model.config.model_type,
+            "max_len": self.model.config.max_position_embeddings,
+            "pooling": "mean",
+            "normalize": self.normalize,
+        })
+    }
+
+    fn config(&self) -> EmbedderConfig {
+        EmbedderConfig {
+            name: "transformer".to_string(),
+            dim: self.dim(),
+            path: Some(self.path.clone()),
+            normalize: self.normalize,
+            batch_size: self.batch_size,
+            ..EmbedderConfig::default()
+        }
+    }
+
+    fn embed_batch(&self, texts: &[&str]) -> Result<Vec<Vec<f32>>> {
+        let mut out = Vec::with_capacity(texts.len());
+        for batch in texts.chunks(self.batch_size) {
+            out.extend(self.model.embed_padded(batch, self.normalize)?);
+        }
+        Ok(out)
+    }
+}
+
+fn numerical(e: candle_core::Error) -> UmapError {
+    UmapError::Numerical(format!("transformer: {e}"))
+}
@@ -10,6 +10,29 @@ pub struct ChunkRecord {
     // Not serialized by default to avoid huge payloads unless needed
     #[serde(skip_serializing)]
     pub vector: Vec<f32>,
+    /// Location in the source document; absent for chunks stored before
+    /// offsets were recorded
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub span: Option<SourceSpan>,
+    /// Heading path of the enclosing section, for chunkers that track one
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub section: Option<String>,
+    /// Code item the chunk holds, from the code chunker
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub symbol: Option<String>,
+}
+
+/// Where a chunk's text sits in its source document.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct SourceSpan {
+    /// Byte offset of the first character
+    pub start: usize,
+    /// Byte offset just past the last character
+    pub end: usize,
+    /// 1-based line of `start`
+    pub start_line: usize,
+    /// 1-based line of the last character
+    pub end_line: usize,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -19,6 +42,12 @@ pub struct Point2D {
     pub chunk_index: i64,
     pub score: f32,
     pub text_preview: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub lines: Option<(usize, usize)>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub section: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub symbol: Option<String>,
     pub x: f32,
     pub y: f32,
 }
@@ -30,7 +59,35 @@ pub struct Point3D {
     pub chunk_index: i64,
     pub score: f32,
     pub text_preview: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub lines: Option<(usize, usize)>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub section: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub symbol: Option<String>,
     pub x: f32,
     pub y: f32,
     pub z: f32,
 }
+
+/// Corpus statistics for one term, as persisted for TF-IDF weighting.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TermStat {
+    pub term: String,
+    /// Number of ingested chunks containing the term
+    pub df: u64,
+    /// Vector component for learned vocabularies; `None` when hashed or out of vocabulary
+    pub index: Option<usize>,
+}
+
+/// How one ingested source was chunked.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SourceRecord {
+    pub source: String,
+    /// Chunker name, e.g. `"token"`
+    pub chunker: String,
+    /// The chunker's params at ingest time
+    pub params: serde_json::Value,
+    pub chunks: i64,
+    pub ingested_at: String,
+}
@@ -0,0 +1,643 @@
+use std::collections::HashMap;
+use std::fs::File;
+use std::io::{BufRead, BufReader, Read};
+use std::path::{Path, PathBuf};
+use std::str::FromStr;
+use std::sync::{Arc, Mutex, OnceLock};
+
+use serde::{Deserialize, Serialize};
+
+use crate::embedding::{Embedder, EmbedderConfig, file_fingerprint, l2_normalize};
+use crate::error::{Result, UmapError};
+use crate::tokenizer::{Tokenizer, TokenizerConfig};
+
+const FASTTEXT_MAGIC: i32 = 793712314;
+const FASTTEXT_VERSION: i32 = 12;
+// Smoothing constant from the SIF paper (Arora et al., 2017)
+const SIF_A: f32 = 1e-3;
+
+/// How token vectors are combined into one text vector.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Pooling {
+    /// Plain average of the token vectors
+    #[default]
+    Mean,
+    /// Smooth inverse frequency: each token weighted by `a / (a + p(w))`
+    Sif,
+}
+
+impl FromStr for Pooling {
+    type Err = UmapError;
+
+    fn from_str(s: &str) -> Result<Self> {
+        match s.to_lowercase().as_str() {
+            "mean" | "average" | "avg" => Ok(Pooling::Mean),
+            "sif" => Ok(Pooling::Sif),
+            other => Err(UmapError::invalid(format!(
+                "unknown pooling: {other} (expected mean or sif)"
+            ))),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum WordVectorFormat {
+    /// GloVe `.txt`, or word2vec / fastText `.vec` with a `count dim` header
+    Text,
+    /// word2vec binary (`.bin` from the original C tool)
+    Word2VecBinary,
+    /// fastText binary model, with subword n-gram buckets for OOV tokens
+    FastText,
+}
+
+/// Character n-gram settings of a fastText model.
+struct Subwords {
+    minn: usize,
+    maxn: usize,
+    bucket: usize,
+}
+
+/// A loaded word-vector table. Rows `0..words.len()` are whole words; a
+/// fastText model appends `bucket` subword rows after them.
+pub struct WordVectors {
+    format: WordVectorFormat,
+    dim: usize,
+    index: HashMap<String, usize>,
+    // Unigram probability per word row, for SIF weighting
+    probs: Vec<f32>,
+    matrix: Vec<f32>,
+    subwords: Option<Subwords>,
+}
+
+impl WordVectors {
+    /// Load a vector file, detecting the format from its contents: fastText
+    /// models by their magic number, word2vec binaries by a `.bin` extension,
+    /// anything else as text.
+    pub fn load(path: &Path) -> Result<Self> {
+        let mut head = [0u8; 4];
+        let n = File::open(path)?.read(&mut head)?;
+        if n == 4 && i32::from_le_bytes(head) == FASTTEXT_MAGIC {
+            return Self::load_fasttext(path);
+        }
+        let is_bin = path
+            .extension()
+            .is_some_and(|e| e.eq_ignore_ascii_case("bin"));
+        if is_bin {
+            Self::load_word2vec_binary(path)
+        } else {
+            Self::load_text(path)
+        }
+    }
+
+    pub fn format(&self) -> WordVectorFormat {
+        self.format
+    }
+
+    pub fn dim(&self) -> usize {
+        self.dim
+    }
+
+    pub fn vocab_len(&self) -> usize {
+        self.probs.len()
+    }
+
+    pub fn has_subwords(&self) -> bool {
+        self.subwords.is_some()
+    }
+
+    fn row(&self, i: usize) -> &[f32] {
+        &self.matrix[i * self.dim..(i + 1) * self.dim]
+    }
+
+    /// Vector for `token`, or `None` if it is out of vocabulary and the model
+    /// has no subword buckets (or none of its n-grams hit one).
+    pub fn vector(&self, token: &str) -> Option<Vec<f32>> {
+        let id = self.index.get(token).copied();
+        let Some(sw) = &self.subwords else {
+            return id.map(|i| self.row(i).to_vec());
+        };
+        // fastText: a word is the average of its own row and its n-gram rows
+        let mut rows: Vec<usize> = id.into_iter().collect();
+        rows.extend(
+            char_ngrams(token, sw.minn, sw.maxn)
+                .into_iter()
+                .map(|h| self.vocab_len() + (h as usize % sw.bucket)),
+        );
+        if rows.is_empty() {
+            return None;
+        }
+        let mut v = vec![0f32; self.dim];
+        for &r in &rows {
+            for (acc, x) in v.iter_mut().zip(self.row(r)) {
+                *acc += x;
+            }
+        }
+        let n = rows.len() as f32;
+        v.iter_mut().for_each(|x| *x /= n);
+        Some(v)
+    }
+
+    /// Unigram probability of `token`; OOV tokens get the rarest word's.
+    fn prob(&self, token: &str) -> f32 {
+        match self.index.get(token) {
+            Some(&i) => self.probs[i],
+            None => self.probs.last().copied().unwrap_or(0.0),
+        }
+    }
+
+    fn from_rows(
+        format: WordVectorFormat,
+        dim: usize,
+        words: Vec<String>,
+        matrix: Vec<f32>,
+    ) -> Self {
+        let probs = zipf_probs(words.len());
+        let mut index = HashMap::with_capacity(words.len());
+        for (i, w) in words.into_iter().enumerate() {
+            // Keep the first (most frequent) row when a file repeats a word
+            index.entry(w).or_insert(i);
+        }
+        Self {
+            format,
+            dim,
+            index,
+            probs,
+            matrix,
+            subwords: None,
+        }
+    }
+
+    fn load_text(path: &Path) -> Result<Self> {
+        let reader = BufReader::new(File::open(path)?);
+        let mut dim = 0;
+        let mut words = Vec::new();
+        let mut matrix = Vec::new();
+        for (lineno, line) in reader.lines().enumerate() {
+            let line = line?;
+            let parts: Vec<&str> = line.split_whitespace().collect();
+            if parts.is_empty() {
+                continue;
+            }
+            // word2vec and fastText .vec files start with "count dim"
+            if lineno == 0 && parts.len() == 2 && parts.iter().all(|p| p.parse::<usize>().is_ok()) {
+                dim = parts[1].parse().unwrap_or(0);
+                continue;
+            }
+            if dim == 0 {
+                dim = parts.len() - 1;
+            }
+            if parts.len() <= dim {
+                return Err(bad_file(
+                    path,
+                    format!("line {} has too few values", lineno + 1),
+                ));
+            }
+            // Some GloVe releases contain words with spaces; the last `dim` fields are the vector
+            let split = parts.len() - dim;
+            for p in &parts[split..] {
+                matrix.push(
+                    p.parse::<f32>().map_err(|_| {
+                        bad_file(path, format!("line {}: bad value {p}", lineno + 1))
+                    })?,
+                );
+            }
+            words.push(parts[..split].join(" "));
+        }
+        if words.is_empty() || dim == 0 {
+            return Err(bad_file(path, "no vectors found".to_string()));
+        }
+        Ok(Self::from_rows(WordVectorFormat::Text, dim, words, matrix))
+    }
+
+    fn load_word2vec_binary(path: &Path) -> Result<Self> {
+        let file = File::open(path)?;
+        let file_len = file.metadata()?.len();
+        let mut r = BufReader::new(file);
+        let mut header = String::new();
+        r.read_line(&mut header)?;
+        let mut it = header.split_whitespace().map(|s| s.parse::<usize>());
+        let (Some(Ok(count)), Some(Ok(dim))) = (it.next(), it.next()) else {
+            return Err(bad_file(path, "missing `count dim` header".to_string()));
+        };
+        if dim == 0 {
+            return Err(bad_file(path, "dim is 0".to_string()));
+        }
+        // Each row is at least a one-byte word, a space and the vector
+        let row_bytes = dim.checked_mul(4).and_then(|b| b.checked_add(2));
+        check_size(path, file_len, count, row_bytes)?;
+        let mut words = Vec::with_capacity(count);
+        let mut matrix = Vec::with_capacity(count * dim);
+        let mut buf = vec![0u8; dim * 4];
+        for _ in 0..count {
+            let mut word = Vec::new();
+            loop {
+                let b = read_u8(&mut r)?;
+                match b {
+                    b' ' => break,
+                    // Vectors may be followed by a newline before the next word
+                    b'\n' if word.is_empty() => {}
+                    _ => word.push(b),
+                }
+            }
+            r.read_exact(&mut buf)?;
+            matrix.extend(
+                buf.chunks_exact(4)
+                    .map(|b| f32::from_le_bytes([b[0], b[1], b[2], b[3]])),
+            );
+            words.push(String::from_utf8_lossy(&word).into_owned());
+        }
+        Ok(Self::from_rows(
+            WordVectorFormat::Word2VecBinary,
+            dim,
+            words,
+            matrix,
+        ))
+    }
+
+    fn load_fasttext(path: &Path) -> Result<Self> {
+        let file = File::open(path)?;
+        let file_len = file.metadata()?.len();
+        let mut r = BufReader::new(file);
+        let _magic = read_i32(&mut r)?;
+        let version = read_i32(&mut r)?;
+        if version > FASTTEXT_VERSION {
+            return Err(bad_file(
+                path,
+                format!("unsupported fastText version {version}"),
+            ));
+        }
+        // Args: dim ws epoch minCount neg wordNgrams loss model bucket minn maxn lrUpdateRate t
+        let dim = read_len(&mut r, path, "dim")?;
+        for _ in 0..7 {
+            read_i32(&mut r)?;
+        }
+        let bucket = read_len(&mut r, path, "bucket")?;
+        let minn = read_len(&mut r, path, "minn")?;
+        let maxn = read_len(&mut r, path, "maxn")?;
+        read_i32(&mut r)?;
+        read_f64(&mut r)?;
+
+        // Dictionary: words come first, then labels (supervised models only)
+        let size = read_len(&mut r, path, "dictionary size")?;
+        let nwords = read_len(&mut r, path, "word count")?;
+        let _nlabels = read_i32(&mut r)?;
+        let ntokens = read_i64(&mut r)?.max(1) as f64;
+        let prune_size = read_i64(&mut r)?;
+        if nwords > size {
+            return Err(bad_file(
+                path,
+                format!("{nwords} words in a dictionary of {size}"),
+            ));
+        }
+        // Each entry is at least a NUL-terminated word, a count and a type
+        check_size(path, file_len, size, Some(11))?;
+        let mut words = Vec::with_capacity(nwords);
+        let mut probs = Vec::with_capacity(nwords);
+        for i in 0..size {
+            let mut word = Vec::new();
+            r.read_until(0, &mut word)?;
+            word.pop();
+            let count = read_i64(&mut r)?;
+            let _entry_type = read_u8(&mut r)?;
+            if i < nwords {
+                words.push(String::from_utf8_lossy(&word).into_owned());
+                probs.push((count as f64 / ntokens) as f32);
+            }
+        }
+        if prune_size > 0 {
+            return Err(bad_file(
+                path,
+                "pruned (quantized) models are not supported".to_string(),
+            ));
+        }
+        if read_u8(&mut r)? != 0 {
+            return Err(bad_file(
+                path,
+                "quantized models are not supported".to_string(),
+            ));
+        }
+        let m = read_len64(&mut r, path, "matrix rows")?;
+        let n = read_len64(&mut r, path, "matrix columns")?;
+        if n != dim || Some(m) != nwords.checked_add(bucket) {
+            return Err(bad_file(
+                path,
+                format!(
+                    "input matrix is {m}x{n}, expected {}x{dim}",
+                    nwords.saturating_add(bucket)
+                ),
+            ));
+        }
+        check_size(path, file_len, m, n.checked_mul(4))?;
+        let mut buf = vec![0u8; m * n * 4];
+        r.read_exact(&mut buf)?;
+        let matrix = buf
+            .chunks_exact(4)
+            .map(|b| f32::from_le_bytes([b[0], b[1], b[2], b[3]]))
+            .collect();
+
+        let index = words.into_iter().enumerate().map(|(i, w)| (w, i)).collect();
+        Ok(Self {
+            format: WordVectorFormat::FastText,
+            dim,
+            index,
+            probs,
+            matrix,
+            subwords: (maxn > 0 && bucket > 0).then_some(Subwords { minn, maxn, bucket }),
+        })
+    }
+}
+
+/// Word vectors by path and fingerprint, shared across embedder instances so
+/// the server loads each file once rather than per request, and again only
+/// when it is replaced.
+fn cached_vectors(path: &Path, fingerprint: &str) -> Result<Arc<WordVectors>> {
+    type Cache = HashMap<(PathBuf, String), Arc<WordVectors>>;
+    static CACHE: OnceLock<Mutex<Cache>> = OnceLock::new();
+    let cache = CACHE.get_or_init(|| Mutex::new(HashMap::new()));
+    let mut guard = cache.lock().unwrap_or_else(|e| e.into_inner());
+    let key = (path.to_path_buf(), fingerprint.to_string());
+    if let Some(v) = guard.get(&key) {
+        return Ok(v.clone());
+    }
+    let loaded = Arc::new(WordVectors::load(path)?);
+    guard.retain(|(p, _), _| p != path);
+    guard.insert(key, loaded.clone());
+    Ok(loaded)
+}
+
+/// Averages pre-trained word vectors (GloVe, word2vec, fastText) over the
+/// tokens of a text. The output dimension is the file's; OOV tokens are
+/// skipped unless a fastText model can build them from subword n-grams.
+pub struct WordVectorEmbedder {
+    path: String,
+    fingerprint: String,
+    pooling: Pooling,
+    tokenizer: Tokenizer,
+    vectors: Arc<WordVectors>,
+}
+
+impl WordVectorEmbedder {
+    pub fn open(path: &str, pooling: Pooling) -> Result<Self> {
+        let fingerprint = file_fingerprint(&[Path::new(path)])?;
+        Ok(Self {
+            path: path.to_string(),
+            vectors: cached_vectors(Path::new(path), &fingerprint)?,
+            fingerprint,
+            pooling,
+            tokenizer: Tokenizer::default(),
+        })
+    }
+
+    pub fn with_tokenizer(mut self, config: TokenizerConfig) -> Self {
+        self.tokenizer = Tokenizer::new(config);
+        self
+    }
+
+    fn embed_one(&self, text: &str) -> Vec<f32> {
+        let mut acc = vec![0f32; self.vectors.dim()];
+        for t in self.tokenizer.tokenize(text) {
+            let Some(v) = self.vectors.vector(&t) else {
+                continue;
+            };
+            let w = match self.pooling {
+                Pooling::Mean => 1.0,
+                Pooling::Sif => SIF_A / (SIF_A + self.vectors.prob(&t)),
+            };
+            for (a, x) in acc.iter_mut().zip(&v) {
+                *a += w * x;
+            }
+        }
+        // Cosine scoring ignores scale, so the sum serves as the (weighted) mean
+        l2_normalize(acc)
+    }
+}
+
+impl Embedder for WordVectorEmbedder {
+    fn name(&self) -> &str {
+        "wordvec"
+    }
+
+    fn dim(&self) -> usize {
+        self.vectors.dim()
+    }
+
+    fn params(&self) -> serde_json::Value {
+        serde_json::json!({
+            "path": self.path,
+            "fingerprint": self.fingerprint,
+            "format": self.vectors.format(),
+            "words": self.vectors.vocab_len(),
+            "subwords": self.vectors.has_subwords(),
+            "pooling": self.pooling,
+            "tokenizer": self.tokenizer.config().describe(),
+        })
+    }
+
+    fn config(&self) -> EmbedderConfig {
+        EmbedderConfig {
+            name: "wordvec".to_string(),
+            dim: self.dim(),
+            path: Some(self.path.clone()),
+            pooling: self.pooling,
+            tokenizer: self.tokenizer.config().clone(),
+            ..EmbedderConfig::default()
+        }
+    }
+
+    fn embed_batch(&self, texts: &[&str]) -> Result<Vec<Vec<f32>>> {
+        Ok(texts.iter().map(|t| self.embed_one(t)).collect())
+    }
+}
+
+/// fastText subword hashes for `word`: every character n-gram of `<word>` with
+/// `minn <= n <= maxn`, excluding the bare `<` and `>` boundary markers.
+fn char_ngrams(word: &str, minn: usize, maxn: usize) -> Vec<u32> {
+    let bounded = format!("<{word}>");
+    let chars: Vec<(usize, char)> = bounded.char_indices().collect();
+    let mut out = Vec::new();
+    for i in 0..chars.len() {
+        for n in minn.max(1)..=maxn {
+            if i + n > chars.len() {
+                break;
+            }
+            if n == 1 && (i == 0 || i + n == chars.len()) {
+                continue;
+            }
+            let start = chars[i].0;
+            let end = chars.get(i + n).map_or(bounded.len(), |c| c.0);
+            out.push(fasttext_hash(&bounded.as_bytes()[start..end]));
+        }
+    }
+    out
+}
+
+/// 32-bit FNV-1a as fastText computes it, including its sign extension of
+/// non-ASCII bytes.
+fn fasttext_hash(bytes: &[u8]) -> u32 {
+    let mut h: u32 = 2166136261;
+    for &b in bytes {
+        h ^= b as i8 as u32;
+        h = h.wrapping_mul(16777619);
+    }
+    h
+}
+
+/// Text and word2vec files carry no counts but are sorted by frequency, so
+/// estimate unigram probabilities from rank with Zipf's law.
+fn zipf_probs(n: usize) -> Vec<f32> {
+    let harmonic = (n.max(1) as f64).ln() + 0.5772;
+    (1..=n)
+        .map(|rank| (1.0 / (rank as f64 * harmonic)) as f32)
+        .collect()
+}
+
+fn bad_file(path: &Path, reason: String) -> UmapError {
+    UmapError::invalid(format!("word vectors {}: {reason}", path.display()))
+}
+
+/// Rejects a header claiming `rows` rows of `row_bytes` each when the file is
+/// too short to hold them, before anything that size is allocated.
+fn check_size(path: &Path, file_len: u64, rows: usize, row_bytes: Option<usize>) -> Result<()> {
+    let needed = row_bytes.and_then(|b| b.checked_mul(rows));
+    match needed {
+        Some(n) if n as u64 <= file_len => Ok(()),
+        _ => Err(bad_file(
+            path,
+            format!("header claims {rows} rows, more than the {file_len}-byte file holds"),
+        )),
+    }
+}
+
+/// A non-negative count or size from a fastText header.
+fn read_len(r: &mut impl Read, path: &Path, what: &str) -> Result<usize> {
+    let v = read_i32(r)?;
+    usize::try_from(v).map_err(|_| bad_file(path, format!("negative {what} {v}")))
+}
+
+fn read_len64(r: &mut impl Read, path: &Path, what: &str) -> Result<usize> {
+    let v = read_i64(r)?;
+    usize::try_from(v).map_err(|_| bad_file(path, format!("negative {what} {v}")))
+}
+
+fn read_u8(r: &mut impl Read) -> Result<u8> {
+    let mut b = [0u8; 1];
+    r.read_exact(&mut b)?;
+    Ok(b[0])
+}
+
+fn read_i32(r: &mut impl Read) -> Result<i32> {
+    let mut b = [0u8; 4];
+    r.read_exact(&mut b)?;
+    Ok(i32::from_le_bytes(b))
+}
+
+fn read_i64(r: &mut impl Read) -> Result<i64> {
+    let mut b = [0u8; 8];
+    r.read_exact(&mut b)?;
+    Ok(i64::from_le_bytes(b))
+}
+
+fn read_f64(r: &mut impl Read) -> Result<f64> {
+    let mut b = [0u8; 8];
+    r.read_exact(&mut b)?;
+    Ok(f64::from_le_bytes(b))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn replacing_the_file_changes_params_and_vectors() {
+        let path = std::env::temp_dir().join(format!("umap-glove-{}.txt", std::process::id()));
+        let path_str = path.to_str().unwrap();
+        std::fs::write(&path, "cat 1 0\ndog 0 1\n").unwrap();
+        let before = WordVectorEmbedder::open(path_str, Pooling::Mean).unwrap();
+        std::fs::write(&path, "cat 0 1\ndog 1 0\n").unwrap();
+        let after = WordVectorEmbedder::open(path_str, Pooling::Mean).unwrap();
+        std::fs::remove_file(&path).unwrap();
+
+        assert_ne!(before.params(), after.params());
+        assert_ne!(before.embed("cat").unwrap(), after.embed("cat").unwrap());
+    }
+
+    fn temp_file(name: &str, bytes: &[u8]) -> PathBuf {
+        let path = std::env::temp_dir().join(format!("umap-{}-{name}", std::process::id()));
+        std::fs::write(&path, bytes).unwrap();
+        path
+    }
+
+    /// A fastText header with the given dim, bucket count and dictionary sizes.
+    fn fasttext_header(dim: i32, bucket: i32, size: i32, nwords: i32) -> Vec<u8> {
+        let mut b = Vec::new();
+        b.extend(FASTTEXT_MAGIC.to_le_bytes());
+        b.extend(FASTTEXT_VERSION.to_le_bytes());
+        b.extend(dim.to_le_bytes());
+        for _ in 0..7 {
+            b.extend(0i32.to_le_bytes());
+        }
+        b.extend(bucket.to_le_bytes());
+        for _ in 0..3 {
+            b.extend(0i32.to_le_bytes());
+        }
+        b.extend(0f64.to_le_bytes());
+        b.extend(size.to_le_bytes());
+        b.extend(nwords.to_le_bytes());
+        b.extend(0i32.to_le_bytes());
+        b.extend(1i64.to_le_bytes());
+        b.extend(0i64.to_le_bytes());
+        b
+    }
+
+    fn load_err(name: &str, bytes: &[u8]) -> String {
+        let path = temp_file(name, bytes);
+        let result = WordVectors::load(&path);
+        std::fs::remove_file(&path).unwrap();
+        match result {
+            Err(UmapError::InvalidParameter(msg)) => msg,
+            Err(e) => panic!("unexpected error {e}"),
+            Ok(_) => panic!("loaded a malformed file"),
+        }
+    }
+
+    #[test]
+    fn malformed_headers_are_rejected_before_allocating() {
+        let msg = load_err("w2v.bin", b"4000000000 300\ncat ");
+        assert!(msg.contains("more than"), "{msg}");
+        let msg = load_err("w2v.bin", b"1 18446744073709551615\ncat ");
+        assert!(msg.contains("more than"), "{msg}");
+
+        let msg = load_err("neg.bin", &fasttext_header(-1, 0, 0, 0));
+        assert!(msg.contains("negative dim"), "{msg}");
+        let msg = load_err("neg.bin", &fasttext_header(4, 0, i32::MAX, i32::MAX));
+        assert!(msg.contains("more than"), "{msg}");
+        let msg = load_err("neg.bin", &fasttext_header(4, 0, 1, 2));
+        assert!(msg.contains("2 words in a dictionary of 1"), "{msg}");
+        let msg = load_err("neg.bin", &fasttext_header(4, 0, 0, -5));
+        assert!(msg.contains("negative word count"), "{msg}");
+    }
+
+    /// A dictionary-less fastText file whose input matrix is `m x n`.
+    fn fasttext_matrix(bucket: i32, m: i64, n: i64) -> Vec<u8> {
+        let mut b = fasttext_header(4, bucket, 0, 0);
+        b.push(0);
+        b.extend(m.to_le_bytes());
+        b.extend(n.to_le_bytes());
+        b
+    }
+
+    #[test]
+    fn oversized_fasttext_matrix_is_rejected() {
+        let msg = load_err("matrix.bin", &fasttext_matrix(0, 3, 4));
+        assert!(msg.contains("input matrix is 3x4"), "{msg}");
+        let msg = load_err("matrix.bin", &fasttext_matrix(0, 0, -4));
+        assert!(msg.contains("negative matrix columns"), "{msg}");
+        let msg = load_err("matrix.bin", &fasttext_matrix(-1, 0, 4));
+        assert!(msg.contains("negative bucket"), "{msg}");
+        // Matches the header but would need 32 GB
+        let msg = load_err("matrix.bin", &fasttext_matrix(i32::MAX, i32::MAX as i64, 4));
+        assert!(msg.contains("more than"), "{msg}");
+    }
+}
@@ -0,0 +1,126 @@
+#!/usr/bin/env python3
+"""Write a tiny randomly initialized BERT (config, tokenizer, safetensors
+weights) next to this script. Only the standard library is needed, and the
+fixed seed makes the output reproducible."""
+
+import json
+import random
+import struct
+from pathlib import Path
+
+HIDDEN = 8
+INTERMEDIATE = 16
+LAYERS = 1
+HEADS = 2
+MAX_POSITIONS = 32
+WORDS = (
+    "the a cat dog sat on mat ran in park stock prices fell rose today "
+    "market river bank money water and of to is was"
+).split()
+SPECIAL = ["[PAD]", "[UNK]", "[CLS]", "[SEP]"]
+
+here = Path(__file__).parent
+random.seed(7)
+vocab = {tok: i for i, tok in enumerate(SPECIAL + WORDS + [".", ","])}
+
+config = {
+    "architectures": ["BertModel"],
+    "model_type": "bert",
+    "vocab_size": len(vocab),
+    "hidden_size": HIDDEN,
+    "num_hidden_layers": LAYERS,
+    "num_attention_heads": HEADS,
+    "intermediate_size": INTERMEDIATE,
+    "hidden_act": "gelu",
+    "hidden_dropout_prob": 0.0,
+    "attention_probs_dropout_prob": 0.0,
+    "max_position_embeddings": MAX_POSITIONS,
+    "type_vocab_size": 2,
+    "initializer_range": 0.02,
+    "layer_norm_eps": 1e-12,
+    "pad_token_id": 0,
+    "position_embedding_type": "absolute",
+    "use_cache": True,
+    "classifier_dropout": None,
+}
+(here / "config.json").write_text(json.dumps(config, indent=2) + "\n")
+
+tokenizer = {
+    "version": "1.0",
+    "truncation": None,
+    "padding": None,
+    "added_tokens": [
+        {
+            "id": vocab[t],
+            "content": t,
+            "single_word": False,
+            "lstrip": False,
+            "rstrip": False,
+            "normalized": False,
+            "special": True,
+        }
+        for t in SPECIAL
+    ],
+    "normalizer": {
+        "type": "BertNormalizer",
+        "clean_text": True,
+        "handle_chinese_chars": True,
+        "strip_accents": None,
+        "lowercase": True,
+    },
+    "pre_tokenizer": {"type": "BertPreTokenizer"},
+    "post_processor": {
+        "type": "BertProcessing",
+        "sep": ["[SEP]", vocab["[SEP]"]],
+        "cls": ["[CLS]", vocab["[CLS]"]],
+    },
+    "decoder": None,
+    "model": {"type": "WordLevel", "vocab": vocab, "unk_token": "[UNK]"},
+}
+(here / "tokenizer.json").write_text(json.dumps(tokenizer, indent=2) + "\n")
+
+tensors = {}
+
+
+def add(name, *shape, fill=None):
+    n = 1
+    for s in shape:
+        n *= s
+    values = [fill] * n if fill is not None else [random.gauss(0, 0.5) for _ in range(n)]
+    tensors[name] = (list(shape), values)
+
+
+def linear(prefix, inputs, outputs):
+    add(f"{prefix}.weight", outputs, inputs)
+    add(f"{prefix}.bias", outputs)
+
+
+def layer_norm(prefix):
+    add(f"{prefix}.weight", HIDDEN, fill=1.0)
+    add(f"{prefix}.bias", HIDDEN, fill=0.0)
+
+
+add("embeddings.word_embeddings.weight", len(vocab), HIDDEN)
+add("embeddings.position_embeddings.weight", MAX_POSITIONS, HIDDEN)
+add("embeddings.token_type_embeddings.weight", 2, HIDDEN)
+layer_norm("embeddings.LayerNorm")
+for i in range(LAYERS):
+    p = f"encoder.layer.{i}"
+    for part in ("query", "key", "value"):
+        linear(f"{p}.attention.self.{part}", HIDDEN, HIDDEN)
+    linear(f"{p}.attention.output.dense", HIDDEN, HIDDEN)
+    layer_norm(f"{p}.attention.output.LayerNorm")
+    linear(f"{p}.intermediate.dense", HIDDEN, INTERMEDIATE)
+    linear(f"{p}.output.dense", INTERMEDIATE, HIDDEN)
+    layer_norm(f"{p}.output.LayerNorm")
+
+header = {}
+data = bytearray()
+for name in sorted(tensors):
+    shape, values = tensors[name]
+    start = len(data)
+    data += struct.pack(f"<{len(values)}f", *values)
+    header[name] = {"dtype": "F32", "shape": shape, "data_offsets": [start, len(data)]}
+raw = json.dumps(header, separators=(",", ":")).encode()
+raw += b" " * (-len(raw) % 8)
+(here / "model.safetensors").write_bytes(struct.pack("<Q", len(raw)) + raw + bytes(data))
@@ -0,0 +1,273 @@
+//! RemoteEmbedder against a local HTTP server speaking the OpenAI and Ollama
+//! embedding APIs.
+
+use std::io::{BufRead, BufReader, Read, Write};
+use std::net::{TcpListener, TcpStream};
+use std::sync::{Arc, Mutex};
+use std::thread;
+use std::time::Duration;
+
+use serde_json::{Value, json};
+use umap_core::{Embedder, RemoteApi, RemoteEmbedder, UmapError};
+
+/// What the server does with the `n`th request (counting from 0).
+enum Reply {
+    Json(u16, Value),
+    /// Wait this long before answering, to trip the client timeout
+    Stall(Duration),
+}
+
+struct MockServer {
+    endpoint: String,
+    /// Path and JSON body of every request received
+    requests: Arc<Mutex<Vec<(String, Value)>>>,
+}
+
+impl MockServer {
+    fn start(reply: impl Fn(usize, &str, &Value) -> Reply + Send + 'static) -> Self {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let endpoint = format!("http://{}", listener.local_addr().unwrap());
+        let requests = Arc::new(Mutex::new(Vec::new()));
+        let log = requests.clone();
+        thread::spawn(move || {
+            for stream in listener.incoming() {
+                let Ok(stream) = stream else { return };
+                let Some((path, body)) = read_request(&stream) else {
+                    continue;
+                };
+                let n = {
+                    let mut log = log.lock().unwrap();
+                    log.push((path.clone(), body.clone()));
+                    log.len() - 1
+                };
+                match reply(n, &path, &body) {
+                    Reply::Json(status, value) => respond(stream, status, &value),
+                    // Answer late on another thread so later requests are served
+                    Reply::Stall(wait) => {
+                        thread::spawn(move || {
+                            thread::sleep(wait);
+                            respond(stream, 200, &json!({}));
+                        });
+                    }
+                }
+            }
+        });
+        Self { endpoint, requests }
+    }
+
+    fn requests(&self) -> Vec<(String, Value)> {
+        self.requests.lock().unwrap().clone()
+    }
+}
+
+fn read_request(stream: &TcpStream) -> Option<(String, Value)> {
+    let mut reader = BufReader::new(stream);
+    let mut line = String::new();
+    reader.read_line(&mut line).ok()?;
+    let path = line.split_whitespace().nth(1)?.to_string();
+    let mut length = 0;
+    loop {
+        line.clear();
+        reader.read_line(&mut line).ok()?;
+        if line.trim().is_empty() {
+            break;
+        }
+        if let Some((name, value)) = line.split_once(':')
+            && name.eq_ignore_ascii_case("content-length")
+        {
+            length = value.trim().parse().ok()?;
+        }
+    }
+    let mut body = vec![0; length];
+    reader.read_exact(&mut body).ok()?;
+    Some((path, serde_json::from_slice(&body).ok()?))
+}
+
+fn respond(mut stream: TcpStream, status: u16, body: &Value) {
+    let body = body.to_string();
+    let _ = write!(
+        stream,
+        "HTTP/1.1 {status} X\r\nContent-Type: application/json\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{body}",
+        body.len()
+    );
+}
+
+/// A 4-dim vector telling texts apart by length.
+fn vector(text: &str) -> Value {
+    json!([text.len() as f32, 1.0, 0.0, 0.0])
+}
+
+/// Answer like an OpenAI server, listing the embeddings in reverse order.
+fn openai(body: &Value) -> Reply {
+    let inputs = body["input"].as_array().unwrap();
+    let data: Vec<Value> = inputs
+        .iter()
+        .enumerate()
+        .rev()
+        .map(|(i, t)| json!({ "index": i, "embedding": vector(t.as_str().unwrap()) }))
+        .collect();
+    Reply::Json(200, json!({ "data": data }))
+}
+
+fn ollama(body: &Value) -> Reply {
+    Reply::Json(
+        200,
+        json!({ "embedding": vector(body["prompt"].as_str().unwrap()) }),
+    )
+}
+
+fn open(server: &MockServer, api: RemoteApi, dim: usize, batch_size: usize) -> RemoteEmbedder {
+    open_with(server, api, dim, batch_size, 5, 3)
+}
+
+fn open_with(
+    server: &MockServer,
+    api: RemoteApi,
+    dim: usize,
+    batch_size: usize,
+    timeout_secs: u64,
+    max_retries: u32,
+) -> RemoteEmbedder {
+    RemoteEmbedder::open(
+        api,
+        &server.endpoint,
+        "test-model",
+        dim,
+        batch_size,
+        timeout_secs,
+        max_retries,
+    )
+    .unwrap()
+}
+
+const TEXTS: &[&str] = &["a", "bb", "ccc", "dddd", "eeeee"];
+
+#[test]
+fn openai_requests_are_batched() {
+    let server = MockServer::start(|_, _, body| openai(body));
+    let embedder = open(&server, RemoteApi::OpenAi, 0, 2);
+    assert_eq!(embedder.dim(), 4);
+
+    let vectors = embedder.embed_batch(TEXTS).unwrap();
+    let lengths: Vec<f32> = vectors.iter().map(|v| v[0]).collect();
+    assert_eq!(lengths, [1.0, 2.0, 3.0, 4.0, 5.0]);
+
+    let requests = server.requests();
+    // The dimension probe, then batches of two
+    let sizes: Vec<usize> = requests
+        .iter()
+        .map(|(_, body)| body["input"].as_array().unwrap().len())
+        .collect();
+    assert_eq!(sizes, [1, 2, 2, 1]);
+    for (path, body) in &requests {
+        assert_eq!(path, "/v1/embeddings");
+        assert_eq!(body["model"], "test-model");
+    }
+}
+
+#[test]
+fn ollama_sends_one_prompt_per_request() {
+    let server = MockServer::start(|_, _, body| ollama(body));
+    let embedder = open(&server, RemoteApi::Ollama, 0, 8);
+    assert_eq!(embedder.dim(), 4);
+
+    let vectors = embedder.embed_batch(&TEXTS[..3]).unwrap();
+    assert_eq!(
+        vectors,
+        [
+            [1.0, 1.0, 0.0, 0.0],
+            [2.0, 1.0, 0.0, 0.0],
+            [3.0, 1.0, 0.0, 0.0]
+        ]
+    );
+
+    let requests = server.requests();
+    assert_eq!(requests.len(), 4);
+    let prompts: Vec<&str> = requests[1..]
+        .iter()
+        .map(|(path, body)| {
+            assert_eq!(path, "/api/embeddings");
+            body["prompt"].as_str().unwrap()
+        })
+        .collect();
+    assert_eq!(prompts, &TEXTS[..3]);
+}
+
+#[test]
+fn known_dim_skips_the_probe() {
+    let server = MockServer::start(|_, _, body| openai(body));
+    let embedder = open(&server, RemoteApi::OpenAi, 4, 8);
+    assert_eq!(embedder.dim(), 4);
+    assert!(server.requests().is_empty());
+}
+
+#[test]
+fn server_errors_and_rate_limits_are_retried() {
+    let server = MockServer::start(|n, _, body| match n {
+        0 => Reply::Json(503, json!({ "error": "loading" })),
+        1 => Reply::Json(429, json!({ "error": "slow down" })),
+        _ => openai(body),
+    });
+    let embedder = open(&server, RemoteApi::OpenAi, 4, 8);
+    let vectors = embedder.embed_batch(&TEXTS[..2]).unwrap();
+    assert_eq!(vectors.len(), 2);
+    assert_eq!(server.requests().len(), 3);
+}
+
+#[test]
+fn retries_stop_at_max_retries() {
+    let server = MockServer::start(|_, _, _| Reply::Json(500, json!({ "error": "down" })));
+    let embedder = open_with(&server, RemoteApi::OpenAi, 4, 8, 5, 1);
+    let err = embedder.embed_batch(&TEXTS[..1]).unwrap_err();
+    assert!(
+        matches!(err, UmapError::Remote(ref m) if m.contains("500")),
+        "{err}"
+    );
+    assert_eq!(server.requests().len(), 2);
+}
+
+#[test]
+fn client_errors_are_not_retried() {
+    let server = MockServer::start(|_, _, _| Reply::Json(400, json!({ "error": "bad model" })));
+    let embedder = open(&server, RemoteApi::OpenAi, 4, 8);
+    let err = embedder.embed_batch(&TEXTS[..1]).unwrap_err();
+    assert!(
+        matches!(err, UmapError::Remote(ref m) if m.contains("bad model")),
+        "{err}"
+    );
+    assert_eq!(server.requests().len(), 1);
+}
+
+#[test]
+fn slow_responses_time_out() {
+    let server = MockServer::start(|_, _, _| Reply::Stall(Duration::from_secs(5)));
+    let embedder = open_with(&server, RemoteApi::OpenAi, 4, 8, 1, 0);
+    let started = std::time::Instant::now();
+    let err = embedder.embed_batch(&TEXTS[..1]).unwrap_err();
+    assert!(matches!(err, UmapError::Remote(_)), "{err}");
+    assert!(started.elapsed() < Duration::from_secs(4));
+}
+
+#[test]
+fn vectors_of_another_size_are_rejected() {
+    // The probe sees 4 dims, later answers have 3
+    let server = MockServer::start(|n, _, body| match n {
+        0 => openai(body),
+        _ => Reply::Json(
+            200,
+            json!({ "data": [{ "index": 0, "embedding": [1.0, 2.0, 3.0] }] }),
+        ),
+    });
+    let embedder = open(&server, RemoteApi::OpenAi, 0, 8);
+    let err = embedder.embed_batch(&TEXTS[..1]).unwrap_err();
+    assert!(
+        matches!(
+            err,
+            UmapError::DimensionMismatch {
+                expected: 4,
+                actual: 3
+            }
+        ),
+        "{err}"
+    );
+}
@@ -0,0 +1,44 @@
+#![cfg(feature = "transformers")]
+
+use umap_core::{Embedder, TransformerEmbedder};
+
+// Hidden size 8, one layer, random weights; see generate.py
+const FIXTURE: &str = concat!(env!("CARGO_MANIFEST_DIR"), "/tests/fixtures/tiny-bert");
+
+const TEXTS: &[&str] = &[
+    "The cat sat on the mat.",
+    "Stock prices fell today",
+    "a dog ran in the park and the cat ran to the river bank",
+    "",
+];
+
+#[test]
+fn dim_is_the_hidden_size() {
+    let embedder = TransformerEmbedder::open(FIXTURE, true, 2).unwrap();
+    assert_eq!(embedder.dim(), 8);
+    for v in embedder.embed_batch(TEXTS).unwrap() {
+        assert_eq!(v.len(), 8);
+    }
+}
+
+#[test]
+fn normalized_outputs_have_unit_length() {
+    let embedder = TransformerEmbedder::open(FIXTURE, true, 4).unwrap();
+    for v in embedder.embed_batch(TEXTS).unwrap() {
+        let norm = v.iter().map(|x| x * x).sum::<f32>().sqrt();
+        assert!((norm - 1.0).abs() < 1e-4, "norm {norm}");
+    }
+}
+
+#[test]
+fn batches_match_single_texts() {
+    // Padding to the longest text in a batch must not change mean pooling
+    let embedder = TransformerEmbedder::open(FIXTURE, false, 4).unwrap();
+    let batch = embedder.embed_batch(TEXTS).unwrap();
+    for (text, batched) in TEXTS.iter().zip(&batch) {
+        let single = embedder.embed(text).unwrap();
+        for (a, b) in single.iter().zip(batched) {
+            assert!((a - b).abs() < 1e-4, "{text:?}: {single:?} vs {batched:?}");
+        }
+    }
+}
@@ -11,28 +11,137 @@ use yew_plotly::plotly::{
 
 #[derive(Deserialize, Debug, Clone)]
 struct Point2D {
-    #[serde(rename = "id")]
-    _id: i64,
+    id: i64,
     source: String,
     chunk_index: i64,
     score: f32,
     text_preview: String,
+    // First and last source line, when the server recorded offsets
+    #[serde(default)]
+    lines: Option<(usize, usize)>,
+    // Heading path, for chunks from section-aware chunkers
+    #[serde(default)]
+    section: Option<String>,
+    // Code item name, for chunks from the code chunker
+    #[serde(default)]
+    symbol: Option<String>,
     x: f32,
     y: f32,
 }
 
 #[derive(Deserialize, Debug, Clone)]
 struct Point3D {
-    #[serde(rename = "id")]
-    _id: i64,
+    id: i64,
     source: String,
     chunk_index: i64,
     score: f32,
     text_preview: String,
+    // First and last source line, when the server recorded offsets
+    #[serde(default)]
+    lines: Option<(usize, usize)>,
+    // Heading path, for chunks from section-aware chunkers
+    #[serde(default)]
+    section: Option<String>,
+    // Code item name, for chunks from the code chunker
+    #[serde(default)]
+    symbol: Option<String>,
     x: f32,
     y: f32,
     z: f32,
 }
+/// "file.txt:L12-18" when line numbers are known, else "file.txt:3" by chunk index.
+fn location(source: &str, chunk_index: i64, lines: Option<(usize, usize)>) -> String {
+    match lines {
+        Some((start, end)) if start == end => format!("{source}:L{start}"),
+        Some((start, end)) => format!("{source}:L{start}-{end}"),
+        None => format!("{source}:{chunk_index}"),
+    }
+}
+
+/// Tooltip for one point: location, section path or code symbol when known,
+/// preview and score.
+fn hover_text(
+    source: &str,
+    chunk_index: i64,
+    lines: Option<(usize, usize)>,
+    context: Option<&str>,
+    preview: &str,
+    score: f32,
+) -> String {
+    let context = context.map(|s| format!(" [{s}]")).unwrap_or_default();
+    format!(
+        "{}{} — {} (similarity: {:.3})",
+        location(source, chunk_index, lines),
+        context,
+        preview,
+        score
+    )
+}
+
+/// Blue (low) -> Cyan -> Green -> Yellow -> Red (high), normalized within the result set.
+fn score_colors(scores: &[f64]) -> (Vec<String>, f64, f64) {
+    let min_score = scores.iter().cloned().fold(f64::INFINITY, f64::min);
+    let max_score = scores.iter().cloned().fold(f64::NEG_INFINITY, f64::max);
+    let score_range = (max_score - min_score).max(0.001);
+    let colors = scores
+        .iter()
+        .map(|&s| {
+            let norm = ((s - min_score) / score_range).clamp(0.0, 1.0);
+            let r = (norm * 255.0) as u8;
+            let g = (if norm < 0.5 {
+                norm * 2.0
+            } else {
+                2.0 - norm * 2.0
+            } * 255.0) as u8;
+            let b = ((1.0 - norm) * 255.0) as u8;
+            format!("rgb({},{},{})", r, g, b)
+        })
+        .collect();
+    (colors, min_score, max_score)
+}
+
+const SECTION_PALETTE: [&str; 9] = [
+    "#1f77b4", "#ff7f0e", "#2ca02c", "#d62728", "#9467bd", "#8c564b", "#e377c2", "#bcbd22",
+    "#17becf",
+];
+
+/// A stable color per top-level section, so chunks of one part of a document
+/// share a color across searches; chunks without a section are light grey.
+fn section_color(section: Option<&str>) -> String {
+    let Some(section) = section else {
+        return "#cccccc".into();
+    };
+    let top = section.split(" > ").next().unwrap_or(section);
+    // FNV-1a: cheap and stable across runs
+    let hash = top.bytes().fold(0xcbf29ce484222325u64, |h, b| {
+        (h ^ b as u64).wrapping_mul(0x100000001b3)
+    });
+    SECTION_PALETTE[(hash % SECTION_PALETTE.len() as u64) as usize].into()
+}
+
+/// Marker colors and plot title for the chosen coloring.
+fn point_colors<'a>(
+    color_by: &str,
+    scores: &[f64],
+    sections: impl Iterator<Item = Option<&'a str>>,
+) -> (Vec<String>, String) {
+    if color_by == "section" {
+        (
+            sections.map(section_color).collect(),
+            "Colored by section".into(),
+        )
+    } else {
+        let (colors, min_score, max_score) = score_colors(scores);
+        (
+            colors,
+            format!(
+                "Scores: {:.3}-{:.3} (Red=High, Blue=Low)",
+                min_score, max_score
+            ),
+        )
+    }
+}
+
 #[derive(Deserialize, Debug, Clone)]
 struct Search2DResp {
     points: Vec<Point2D>,
@@ -51,6 +160,9 @@ pub fn app() -> Html {
     let status = use_state(String::new);
     let error_msg = use_state(|| Option::<String>::None);
     let method = use_state(|| String::from("umap"));
+    let color_by = use_state(|| String::from("score"));
+    let chunker = use_state(|| String::from("token"));
+    let gutenberg = use_state(|| false);
     let tokens_per_chunk = use_state(|| 1000usize);
     let overlap = use_state(|| 300usize);
     let n_neighbors = use_state(|| 15usize);
@@ -60,6 +172,9 @@ pub fn app() -> Html {
     let neg_rate = use_state(|| 5usize);
     let repulsion = use_state(|| 1.0f32);
     let spread = use_state(|| 1.0f32);
+    // Previous layout as (dims, JSON anchors) so a new result can be aligned to it
+    let prev_layout = use_state(|| Option::<(usize, String)>::None);
+    let align = use_state(|| true);
     let readers = use_mut_ref(Vec::<FileReader>::new);
 
     let run_search = {
@@ -75,8 +190,11 @@ pub fn app() -> Html {
         let repulsion_state = repulsion.clone();
         let spread_state = spread.clone();
         let method_state = method.clone();
+        let color_by_state = color_by.clone();
         let status_state = status.clone();
         let err_state = error_msg.clone();
+        let prev_layout_state = prev_layout.clone();
+        let align_state = align.clone();
         Callback::from(move |_| {
             let query = (*query).clone();
             let dims_val = *dims;
@@ -90,8 +208,16 @@ pub fn app() -> Html {
             let rep = *repulsion_state;
             let spr = *spread_state;
             let method = (*method_state).clone();
+            let color_by = (*color_by_state).clone();
             let status_state = status_state.clone();
             let err_state = err_state.clone();
+            let prev_layout_state = prev_layout_state.clone();
+            let align_to = match (*align_state, &*prev_layout_state) {
+                (true, Some((d, anchors))) if *d == dims_val => {
+                    format!("&align_to={}", urlencoding::encode(anchors))
+                }
+                _ => String::new(),
+            };
             wasm_bindgen_futures::spawn_local(async move {
                 let url = format!(
                     "/api/search?query={}&k={}&dims={}&method={}&n_neighbors={}&min_dist={}&n_epochs={}&learning_rate={}&negative_sample_rate={}&repulsion_strength={}&spread={}",
@@ -106,7 +232,7 @@ pub fn app() -> Html {
                     ngr,
                     rep,
                     spr
-                );
+                ) + &align_to;
                 info!("search GET {}", &url);
                 match Request::get(&url).send().await {
                     Ok(resp) => {
@@ -123,35 +249,21 @@ pub fn app() -> Html {
                                         .points
                                         .iter()
                                         .map(|p| {
-                                            format!(
-                                                "{}:{} — {} (similarity: {:.3})",
-                                                p.source, p.chunk_index, p.text_preview, p.score
+                                            hover_text(
+                                                &p.source,
+                                                p.chunk_index,
+                                                p.lines,
+                                                p.section.as_deref().or(p.symbol.as_deref()),
+                                                &p.text_preview,
+                                                p.score,
                                             )
                                         })
                                         .collect();
-                                    // Normalize scores to [0,1] range within this result set for better color contrast
-                                    let min_score =
-                                        scores.iter().cloned().fold(f64::INFINITY, f64::min);
-                                    let max_score =
-                                        scores.iter().cloned().fold(f64::NEG_INFINITY, f64::max);
-                                    let score_range = (max_score - min_score).max(0.001);
-                                    let colors: Vec<String> = scores
-                                        .iter()
-                                        .map(|&s| {
-                                            let norm =
-                                                ((s - min_score) / score_range).clamp(0.0, 1.0);
-                                            // Blue (low) -> Cyan -> Green -> Yellow -> Red (high)
-                                            let r = (norm * 255.0) as u8;
-                                            let g = (if norm < 0.5 {
-                                                norm * 2.0
-                                            } else {
-                                                2.0 - norm * 2.0
-                                            } * 255.0)
-                                                as u8;
-                                            let b = ((1.0 - norm) * 255.0) as u8;
-                                            format!("rgb({},{},{})", r, g, b)
-                                        })
-                                        .collect();
+                                    let (colors, title_text) = point_colors(
+                                        &color_by,
+                                        &scores,
+                                        data.points.iter().map(|p| p.section.as_deref()),
+                                    );
                                     let trace = Scatter::new(xs, ys)
                                         .mode(Mode::Markers)
                                         .text_array(texts)
@@ -161,10 +273,6 @@ pub fn app() -> Html {
                                                 .size(10),
                                         );
                                     let mut plt = Plot::new();
-                                    let title_text = format!(
-                                        "Scores: {:.3}-{:.3} (Red=High, Blue=Low)",
-                                        min_score, max_score
-                                    );
                                     let hover_label = Label::new()
                                         .background_color("#fffacd")
                                         .font(Font::new().color("#000000"));
@@ -174,6 +282,17 @@ pub fn app() -> Html {
                                     plt.add_trace(trace);
                                     plt.set_layout(layout);
                                     plot.set(plt);
+                                    let anchors: Vec<serde_json::Value> = data
+                                        .points
+                                        .iter()
+                                        .map(
+                                            |p| serde_json::json!({"id": p.id, "x": p.x, "y": p.y}),
+                                        )
+                                        .collect();
+                                    prev_layout_state.set(Some((
+                                        2,
+                                        serde_json::Value::from(anchors).to_string(),
+                                    )));
                                     status_state
                                         .set(format!("Fetched {} points", data.points.len()));
                                     err_state.set(None);
@@ -198,35 +317,21 @@ pub fn app() -> Html {
                                         .points
                                         .iter()
                                         .map(|p| {
-                                            format!(
-                                                "{}:{} — {} (similarity: {:.3})",
-                                                p.source, p.chunk_index, p.text_preview, p.score
+                                            hover_text(
+                                                &p.source,
+                                                p.chunk_index,
+                                                p.lines,
+                                                p.section.as_deref().or(p.symbol.as_deref()),
+                                                &p.text_preview,
+                                                p.score,
                                             )
                                         })
                                         .collect();
-                                    // Normalize scores to [0,1] range within this result set for better color contrast
-                                    let min_score =
-                                        scores.iter().cloned().fold(f64::INFINITY, f64::min);
-                                    let max_score =
-                                        scores.iter().cloned().fold(f64::NEG_INFINITY, f64::max);
-                                    let score_range = (max_score - min_score).max(0.001);
-                                    let colors: Vec<String> = scores
-                                        .iter()
-                                        .map(|&s| {
-                                            let norm =
-                                                ((s - min_score) / score_range).clamp(0.0, 1.0);
-                                            // Blue (low) -> Cyan -> Green -> Yellow -> Red (high)
-                                            let r = (norm * 255.0) as u8;
-                                            let g = (if norm < 0.5 {
-                                                norm * 2.0
-                                            } else {
-                                                2.0 - norm * 2.0
-                                            } * 255.0)
-                                                as u8;
-                                            let b = ((1.0 - norm) * 255.0) as u8;
-                                            format!("rgb({},{},{})", r, g, b)
-                                        })
-                                        .collect();
+                                    let (colors, title_text) = point_colors(
+                                        &color_by,
+                                        &scores,
+                                        data.points.iter().map(|p| p.section.as_deref()),
+                                    );
                                     let trace = Scatter3D::new(xs, ys, zs)
                                         .mode(Mode::Markers)
                                         .text_array(texts)
@@ -236,10 +341,6 @@ pub fn app() -> Html {
                                                 .size(6),
                                         );
                                     let mut plt = Plot::new();
-                                    let title_text = format!(
-                                        "Scores: {:.3}-{:.3} (Red=High, Blue=Low)",
-                                        min_score, max_score
-                                    );
                                     let hover_label = Label::new()
                                         .background_color("#fffacd")
                                         .font(Font::new().color("#000000"));
@@ -249,6 +350,17 @@ pub fn app() -> Html {
                                     plt.add_trace(trace);
                                     plt.set_layout(layout);
                                     plot.set(plt);
+                                    let anchors: Vec<serde_json::Value> = data
+                                        .points
+                                        .iter()
+                                        .map(|p| {
+                                            serde_json::json!({"id": p.id, "x": p.x, "y": p.y, "z": p.z})
+                                        })
+                                        .collect();
+                                    prev_layout_state.set(Some((
+                                        3,
+                                        serde_json::Value::from(anchors).to_string(),
+                                    )));
                                     status_state
                                         .set(format!("Fetched {} points", data.points.len()));
                                     err_state.set(None);
@@ -296,6 +408,8 @@ pub fn app() -> Html {
         let file_input_ref = file_input_ref.clone();
         let tokens_per_chunk = tokens_per_chunk.clone();
         let overlap = overlap.clone();
+        let chunker = chunker.clone();
+        let gutenberg = gutenberg.clone();
         let status = status.clone();
         let err_state = error_msg.clone();
         let readers = readers.clone();
@@ -308,6 +422,8 @@ pub fn app() -> Html {
                         let filename = f.name();
                         let tpc = *tokens_per_chunk;
                         let ov = *overlap;
+                        let chunker = (*chunker).clone();
+                        let gutenberg = *gutenberg;
                         let status2 = status.clone();
                         let err2 = err_state.clone();
                         let reader = gloo_file::callbacks::read_as_text(&f, move |res| match res {
@@ -315,9 +431,10 @@ pub fn app() -> Html {
                                 let payload = serde_json::json!({
                                     "filename": filename,
                                     "content": text,
+                                    "chunker": chunker,
+                                    "gutenberg": gutenberg,
                                     "tokens_per_chunk": tpc,
                                     "overlap": ov,
-                                    "dim": 512,
                                 });
                                 let status3 = status2.clone();
                                 let err3 = err2.clone();
@@ -375,7 +492,23 @@ pub fn app() -> Html {
                 }) }}>
                     <option value="umap" selected={(*method)=="umap"}>{"UMAP"}</option>
                     <option value="pca" selected={(*method)=="pca"}>{"PCA"}</option>
+                    <option value="random" selected={(*method)=="random"}>{"Random projection (Gaussian)"}</option>
+                    <option value="sparse_random" selected={(*method)=="sparse_random"}>{"Random projection (sparse)"}</option>
+                    <option value="mds" selected={(*method)=="mds"}>{"Classical MDS"}</option>
+                    <option value="isomap" selected={(*method)=="isomap"}>{"Isomap"}</option>
+                </select>
+                <label>{"color:"}</label>
+                <select onchange={{ let color_by = color_by.clone(); Callback::from(move |e: Event| {
+                    let sel: web_sys::HtmlSelectElement = e.target_unchecked_into();
+                    color_by.set(sel.value());
+                }) }}>
+                    <option value="score" selected={(*color_by)=="score"}>{"Similarity"}</option>
+                    <option value="section" selected={(*color_by)=="section"}>{"Section"}</option>
                 </select>
+                <label title="Rotate/reflect the new layout to best match the previous one">
+                    <input type="checkbox" checked={*align} onchange={{ let align = align.clone(); Callback::from(move |_| align.set(!*align)) }} />
+                    {"align"}
+                </label>
                 <label style="margin-left:0.5rem;">{"n_neighbors:"}</label>
                 <input type="number" min="2" max="200" value={n_neighbors.to_string()} oninput={{ let n_neighbors = n_neighbors.clone(); Callback::from(move |e: InputEvent| { let input: web_sys::HtmlInputElement = e.target_unchecked_into(); if let Ok(v)=input.value().parse(){ n_neighbors.set(v);} }) }} />
                 <label>{"min_dist:"}</label>
@@ -397,10 +530,27 @@ pub fn app() -> Html {
             }
             <div style="display:flex; gap: 0.5rem; align-items:center; margin-top:0.5rem;">
                 <input type="file" multiple=true ref={file_input_ref.clone()} />
+                <label>{"chunker:"}</label>
+                <select onchange={{ let chunker = chunker.clone(); Callback::from(move |e: Event| {
+                    let sel: web_sys::HtmlSelectElement = e.target_unchecked_into();
+                    chunker.set(sel.value());
+                }) }}>
+                    <option value="token" selected={(*chunker)=="token"}>{"Token windows"}</option>
+                    <option value="paragraph" selected={(*chunker)=="paragraph"}>{"Paragraphs"}</option>
+                    <option value="sentence" selected={(*chunker)=="sentence"}>{"Sentences"}</option>
+                    <option value="recursive" selected={(*chunker)=="recursive"}>{"Recursive"}</option>
+                    <option value="markdown" selected={(*chunker)=="markdown"}>{"Markdown sections"}</option>
+                    <option value="semantic" selected={(*chunker)=="semantic"}>{"Semantic (topic shifts)"}</option>
+                    <option value="code" selected={(*chunker)=="code"}>{"Code items"}</option>
+                </select>
                 <label>{"tokens/chunk:"}</label>
                 <input type="number" min="100" max="5000" value={tokens_per_chunk.to_string()} oninput={{ let tokens_per_chunk = tokens_per_chunk.clone(); Callback::from(move |e: InputEvent| { let input: web_sys::HtmlInputElement = e.target_unchecked_into(); if let Ok(v)=input.value().parse(){ tokens_per_chunk.set(v);} }) }} />
                 <label>{"overlap:"}</label>
                 <input type="number" min="0" max="4000" value={overlap.to_string()} oninput={{ let overlap = overlap.clone(); Callback::from(move |e: InputEvent| { let input: web_sys::HtmlInputElement = e.target_unchecked_into(); if let Ok(v)=input.value().parse(){ overlap.set(v);} }) }} />
+                <label title="Strip Project Gutenberg license header/footer and record chapters">
+                    <input type="checkbox" checked={*gutenberg} onchange={{ let gutenberg = gutenberg.clone(); Callback::from(move |_| gutenberg.set(!*gutenberg)) }} />
+                    {"Gutenberg"}
+                </label>
                 <button onclick={on_upload}>{"Ingest Files"}</button>
             </div>
             <div style="height: 640px; margin-top: 1rem;">
@@ -40,7 +40,7 @@ if [ -d "tmp" ] && [ -n "$(ls -A tmp/*.txt 2>/dev/null)" ]; then
   for file in tmp/*.txt; do
     echo "  → $(basename "$file")"
     "$BIN" ingest --db "$DB_PATH" --file "$file" \
-      --tokens-per-chunk "$TOKENS_PER_CHUNK" --overlap "$OVERLAP" --dim 512
+      --tokens-per-chunk "$TOKENS_PER_CHUNK" --overlap "$OVERLAP" --dim 512 --gutenberg
   done
   echo ""
 fi